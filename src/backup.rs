@@ -0,0 +1,194 @@
+// src/backup.rs
+use crate::config::BackupRetention;
+use crate::data::{read_data_file, save_data, TimePeriod};
+use crate::events::Event;
+use crate::storage::Storage;
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const ID_FORMAT: &str = "%Y%m%dT%H%M%S%3f";
+
+pub struct Snapshot {
+    pub id: String,
+    pub path: PathBuf,
+    pub taken_at: DateTime<Utc>,
+}
+
+/// Snapshots live in a `backups` directory beside the data file.
+pub fn backup_dir(storage_path: &Path) -> PathBuf {
+    storage_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("backups")
+}
+
+/// Storage wrapper that takes a snapshot after a successful write, unless the
+/// last one is more recent than the retention's interval.
+pub struct BackedUpStorage {
+    inner: Box<dyn Storage>,
+    dir: PathBuf,
+    retention: BackupRetention,
+}
+
+impl BackedUpStorage {
+    pub fn new(inner: Box<dyn Storage>, dir: PathBuf, retention: BackupRetention) -> Self {
+        Self {
+            inner,
+            dir,
+            retention,
+        }
+    }
+}
+
+impl Storage for BackedUpStorage {
+    fn load(&self) -> Result<TimePeriod, Box<dyn Error>> {
+        self.inner.load()
+    }
+
+    fn load_events(&self) -> Result<Vec<Event>, Box<dyn Error>> {
+        self.inner.load_events()
+    }
+
+    fn append_event(&mut self, event: &Event) -> Result<(), Box<dyn Error>> {
+        self.inner.append_event(event)
+    }
+
+    fn save(&mut self, time_period: &TimePeriod) -> Result<(), Box<dyn Error>> {
+        self.inner.save(time_period)?;
+        take_snapshot(&self.dir, time_period, &self.retention)
+    }
+
+    fn save_task(
+        &mut self,
+        time_period: &TimePeriod,
+        category: &str,
+        index: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        self.inner.save_task(time_period, category, index)?;
+        take_snapshot(&self.dir, time_period, &self.retention)
+    }
+
+    fn rebuild(&mut self, time_period: &TimePeriod) -> Result<(), Box<dyn Error>> {
+        self.inner.rebuild(time_period)?;
+        take_snapshot(&self.dir, time_period, &self.retention)
+    }
+}
+
+pub fn take_snapshot(
+    dir: &Path,
+    time_period: &TimePeriod,
+    retention: &BackupRetention,
+) -> Result<(), Box<dyn Error>> {
+    let now = Utc::now();
+    let interval = Duration::minutes(retention.interval_minutes.into());
+    if list_snapshots(dir)?
+        .first()
+        .is_some_and(|newest| now - newest.taken_at < interval)
+    {
+        return Ok(());
+    }
+    fs::create_dir_all(dir)?;
+    // Ids have millisecond resolution; a later one is as good as taken now.
+    let mut taken_at = now;
+    let mut path = snapshot_path(dir, taken_at);
+    while path.exists() {
+        taken_at += Duration::milliseconds(1);
+        path = snapshot_path(dir, taken_at);
+    }
+    save_data(&path, time_period)?;
+    prune(dir, retention)
+}
+
+fn snapshot_path(dir: &Path, taken_at: DateTime<Utc>) -> PathBuf {
+    dir.join(format!("tasks-{}.json", taken_at.format(ID_FORMAT)))
+}
+
+/// Lists snapshots, newest first.
+pub fn list_snapshots(dir: &Path) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut snapshots = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let id = match path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("tasks-"))
+            .and_then(|name| name.strip_suffix(".json"))
+        {
+            Some(id) => id.to_string(),
+            None => continue,
+        };
+        if let Ok(taken_at) = NaiveDateTime::parse_from_str(&id, ID_FORMAT) {
+            snapshots.push(Snapshot {
+                id,
+                path,
+                taken_at: Utc.from_utc_datetime(&taken_at),
+            });
+        }
+    }
+    snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.taken_at));
+    Ok(snapshots)
+}
+
+/// Finds the snapshot whose id starts with `id`.
+pub fn find_snapshot(dir: &Path, id: &str) -> Result<Snapshot, Box<dyn Error>> {
+    let mut matches: Vec<Snapshot> = list_snapshots(dir)?
+        .into_iter()
+        .filter(|snapshot| snapshot.id.starts_with(id))
+        .collect();
+    match matches.len() {
+        0 => Err(format!("No backup matches {:?}", id).into()),
+        1 => Ok(matches.remove(0)),
+        n => Err(format!("{} backups match {:?}; use a longer id", n, id).into()),
+    }
+}
+
+pub fn read_snapshot(snapshot: &Snapshot) -> Result<TimePeriod, Box<dyn Error>> {
+    read_data_file(&snapshot.path)
+}
+
+fn prune(dir: &Path, retention: &BackupRetention) -> Result<(), Box<dyn Error>> {
+    let snapshots = list_snapshots(dir)?;
+    let mut keep: HashSet<&str> = snapshots
+        .iter()
+        .take(retention.recent)
+        .map(|snapshot| snapshot.id.as_str())
+        .collect();
+    keep.extend(oldest_per_slot(&snapshots, "%Y%m%d%H", retention.hourly));
+    keep.extend(oldest_per_slot(&snapshots, "%Y%m%d", retention.daily));
+
+    for snapshot in &snapshots {
+        if !keep.contains(snapshot.id.as_str()) {
+            fs::remove_file(&snapshot.path)?;
+        }
+    }
+    Ok(())
+}
+
+/// For the newest `slots` distinct time slots, the id of the oldest snapshot
+/// in each. `snapshots` must be sorted newest first.
+fn oldest_per_slot<'a>(snapshots: &'a [Snapshot], slot_format: &str, slots: usize) -> Vec<&'a str> {
+    let mut kept: Vec<(String, &str)> = Vec::new();
+    for snapshot in snapshots {
+        let slot = snapshot.taken_at.format(slot_format).to_string();
+        if let Some((last_slot, id)) = kept.last_mut() {
+            if *last_slot == slot {
+                *id = &snapshot.id;
+                continue;
+            }
+        }
+        if kept.len() == slots {
+            break;
+        }
+        kept.push((slot, &snapshot.id));
+    }
+    kept.into_iter().map(|(_, id)| id).collect()
+}
@@ -1,9 +1,9 @@
 // src/categorization
 use crate::data::Task;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Categorization {
     pub categories: HashMap<String, Vec<Task>>,
 }
@@ -23,20 +23,60 @@ impl Categorization {
     }
 
     pub fn add_task_to_category(&mut self, task: Task) {
-        let (category, task_description) = Self::extract_category_from_description(&task.name);
+        let (category, task_description, tags) = Self::parse_description(&task.name);
         let mut task = task.clone();
         task.name = task_description;
+        task.tags.extend(tags);
         let category = &category;
 
-        if let Some(tasks) = self.categories.get_mut(&category.clone()) {
-            tasks.push(task);
-        } else {
-            // If category doesn't exist, create it and add the task
-            self.add_category(category.clone());
-            self.add_task_to_category(task);
+        // Create the category if it doesn't exist yet, then add the task
+        self.add_category(category.clone());
+        self.categories.get_mut(category).unwrap().push(task);
+    }
+
+    /// Splits `Fix login #client-a #bugfix` into its primary category (the
+    /// first `#word`, or Uncategorized), the name without any `#words`, and
+    /// the remaining tags without their `#`.
+    pub fn parse_description(description: &str) -> (String, String, BTreeSet<String>) {
+        let mut category = None;
+        let mut tags = BTreeSet::new();
+        let mut name = Vec::new();
+        for word in description.split_whitespace() {
+            match word.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+                Some(_) if category.is_none() => category = Some(word.to_string()),
+                Some(tag) => {
+                    tags.insert(tag.to_string());
+                }
+                None => name.push(word),
+            }
+        }
+        (
+            category.unwrap_or_else(|| String::from("Uncategorized")),
+            name.join(" "),
+            tags,
+        )
+    }
+
+    /// The levels of a category, e.g. `#acme/website` is `["acme", "website"]`.
+    /// Uncategorized is a single top-level entry.
+    pub fn category_path(category: &str) -> Vec<&str> {
+        match category.strip_prefix('#') {
+            Some(path) => path.split('/').filter(|level| !level.is_empty()).collect(),
+            None => vec![category],
+        }
+    }
+
+    /// Whether `category` is `ancestor` or nested somewhere below it.
+    pub fn is_within(category: &str, ancestor: &str) -> bool {
+        if !category.starts_with('#') || !ancestor.starts_with('#') {
+            return category == ancestor;
         }
+        Self::category_path(category).starts_with(&Self::category_path(ancestor))
     }
 
+    /// How descriptions were read before tags existed: only the first `#word`
+    /// counts, and the rest is glued back together. Kept so that old `Start`
+    /// events replay exactly as they were recorded.
     pub fn extract_category_from_description(description: &str) -> (String, String) {
         let mut category = String::from("Uncategorized");
         let mut task_description = description.to_string();
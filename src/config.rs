@@ -3,10 +3,109 @@ use serde::{Deserialize, Serialize};
 use std::fs::{self, File};
 use std::io::{BufReader, BufWriter};
 use std::path::PathBuf;
+use std::str::FromStr;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct AppConfig {
     pub storage_location: Option<PathBuf>,
+    #[serde(default)]
+    pub storage_backend: StorageBackend,
+    #[serde(default)]
+    pub backup_retention: BackupRetention,
+    #[serde(default)]
+    pub running_policy: RunningPolicy,
+}
+
+/// What happens to a running task when another one starts or resumes.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum RunningPolicy {
+    /// Stop the running task, so only one task ever runs.
+    #[default]
+    AutoStop,
+    /// Pause the running task, so it can be resumed later.
+    AutoPause,
+    /// Leave it running; tasks may overlap.
+    Parallel,
+}
+
+impl FromStr for RunningPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "stop" | "auto-stop" => Ok(RunningPolicy::AutoStop),
+            "pause" | "auto-pause" => Ok(RunningPolicy::AutoPause),
+            "parallel" => Ok(RunningPolicy::Parallel),
+            other => Err(format!(
+                "Unknown running policy: {} (expected stop, pause or parallel)",
+                other
+            )),
+        }
+    }
+}
+
+/// How many rolling snapshots of the data to keep. Hourly and daily slots keep
+/// the oldest snapshot taken in that hour or day.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BackupRetention {
+    pub recent: usize,
+    pub hourly: usize,
+    pub daily: usize,
+    /// Minutes after a snapshot during which saves take no new one.
+    #[serde(default = "default_backup_interval")]
+    pub interval_minutes: u32,
+}
+
+fn default_backup_interval() -> u32 {
+    5
+}
+
+impl Default for BackupRetention {
+    fn default() -> Self {
+        Self {
+            recent: 10,
+            hourly: 24,
+            daily: 30,
+            interval_minutes: default_backup_interval(),
+        }
+    }
+}
+
+impl BackupRetention {
+    pub fn is_disabled(&self) -> bool {
+        self.recent == 0 && self.hourly == 0 && self.daily == 0
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum StorageBackend {
+    #[default]
+    Json,
+    Sqlite,
+}
+
+impl StorageBackend {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            StorageBackend::Json => "tasks.json",
+            StorageBackend::Sqlite => "tasks.db",
+        }
+    }
+}
+
+impl FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(StorageBackend::Json),
+            "sqlite" => Ok(StorageBackend::Sqlite),
+            other => Err(format!(
+                "Unknown storage backend: {} (expected json or sqlite)",
+                other
+            )),
+        }
+    }
 }
 
 impl AppConfig {
@@ -35,13 +134,14 @@ impl AppConfig {
         serde_json::to_writer(writer, self)?;
         Ok(())
     }
-}
 
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self {
-            storage_location: None,
-        }
+    pub fn storage_path(&self) -> PathBuf {
+        self.storage_location.clone().unwrap_or_else(|| {
+            let mut default_path = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+            default_path.push("time_tracker");
+            default_path.push(self.storage_backend.file_name());
+            default_path
+        })
     }
 }
 
@@ -1,22 +1,49 @@
 use crate::categorization::Categorization;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
 use std::error::Error;
-use std::fs::File;
-use std::io::{BufReader, BufWriter};
-use std::path::PathBuf;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct TimeChunk {
+    /// Empty for pauses, which are never addressed on their own.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
     pub start_time: DateTime<Utc>,
     pub end_time: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<Note>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// A remark on a task or a single chunk, e.g. "blocked on review".
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Note {
+    pub at: DateTime<Utc>,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Task {
+    #[serde(default)]
+    pub id: String,
     pub name: String,
+    /// Tags besides the primary category, without their `#`.
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    /// Notes on the task as a whole; chunks carry their own.
+    #[serde(default)]
+    pub notes: Vec<Note>,
     pub time_chunks: Vec<TimeChunk>,
+    /// Intervals spent paused; the last one is open while the task is paused.
+    #[serde(default)]
+    pub pauses: Vec<TimeChunk>,
+    /// Total of the closed `pauses`.
     pub paused_duration: Duration,
     pub status: TaskStatus,
 }
@@ -28,46 +55,338 @@ pub enum TaskStatus {
     Stopped,
 }
 
-// Update the TimePeriod struct
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TimePeriod {
     pub categorization: Categorization,
 }
 
+/// A fresh random ID, shown abbreviated like a git hash.
+pub fn new_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+/// A stable ID computed from `parts`, for data that predates IDs. Uses
+/// FNV-1a so the result never changes between builds.
+fn derived_id(parts: &[&str]) -> String {
+    let hash = parts
+        .join("\u{1f}")
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
+
+/// Shortest prefix that tells an ID apart from all others, like git does.
+const MIN_SHORT_ID: usize = 4;
+
+impl TimeChunk {
+    /// A chunk with a fresh ID.
+    pub fn new(start_time: DateTime<Utc>, end_time: Option<DateTime<Utc>>) -> TimeChunk {
+        TimeChunk {
+            id: new_id(),
+            start_time,
+            end_time,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Length of the chunk, counting a chunk that is still open up to `now`.
+    pub fn duration(&self, now: DateTime<Utc>) -> chrono::Duration {
+        self.end_time.unwrap_or(now) - self.start_time
+    }
+
+    /// How much of the chunk falls between `from` and `to`.
+    pub fn duration_within(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> chrono::Duration {
+        let start = self.start_time.max(from);
+        let end = self.end_time.unwrap_or(now).min(to);
+        (end - start).max(chrono::Duration::zero())
+    }
+
+    /// Whether the chunk shares any time with `start..end`, counting a chunk
+    /// that is still open up to `now`.
+    pub fn overlaps(&self, start: DateTime<Utc>, end: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        self.start_time < end && self.end_time.unwrap_or(now) > start
+    }
+
+    /// What is left of the chunk once `start..end` is cut out of it: nothing,
+    /// one piece, or two pieces either side. An open chunk stays open, and
+    /// both pieces keep the chunk's notes.
+    pub fn without(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<TimeChunk> {
+        if self.start_time >= end || self.end_time.is_some_and(|chunk_end| chunk_end <= start) {
+            return vec![self.clone()];
+        }
+        let mut pieces = Vec::new();
+        if self.start_time < start {
+            pieces.push(TimeChunk {
+                end_time: Some(start),
+                ..self.clone()
+            });
+        }
+        if self.end_time.is_none_or(|chunk_end| chunk_end > end) {
+            // The chunk keeps its ID on whichever piece comes first.
+            let id = if pieces.is_empty() {
+                self.id.clone()
+            } else {
+                new_id()
+            };
+            pieces.push(TimeChunk {
+                id,
+                start_time: end,
+                ..self.clone()
+            });
+        }
+        pieces
+    }
+}
+
+impl TimePeriod {
+    pub fn task_count(&self) -> usize {
+        self.categorization.categories.values().map(Vec::len).sum()
+    }
+
+    pub fn total_tracked(&self, now: DateTime<Utc>) -> chrono::Duration {
+        self.categorization
+            .categories
+            .values()
+            .flatten()
+            .fold(chrono::Duration::zero(), |acc, task| {
+                acc + task.tracked(now)
+            })
+    }
+
+    /// Gives every task and chunk that predates IDs one derived from its
+    /// contents, so replaying or migrating the same data always yields the
+    /// same IDs.
+    pub fn assign_missing_ids(&mut self) {
+        for (category, tasks) in self.categorization.categories.iter_mut() {
+            for task in tasks {
+                if task.id.is_empty() {
+                    let first_start = task
+                        .time_chunks
+                        .first()
+                        .map(|chunk| chunk.start_time.to_rfc3339())
+                        .unwrap_or_default();
+                    task.id = derived_id(&[category, &task.name, &first_start]);
+                }
+                for (position, chunk) in task.time_chunks.iter_mut().enumerate() {
+                    if chunk.id.is_empty() {
+                        chunk.id = derived_id(&[
+                            &task.id,
+                            &position.to_string(),
+                            &chunk.start_time.to_rfc3339(),
+                        ]);
+                    }
+                }
+            }
+        }
+    }
+
+    fn all_ids(&self) -> impl Iterator<Item = &str> {
+        self.categorization
+            .categories
+            .values()
+            .flatten()
+            .flat_map(|task| {
+                std::iter::once(task.id.as_str())
+                    .chain(task.time_chunks.iter().map(|chunk| chunk.id.as_str()))
+            })
+    }
+
+    /// Maps every task and chunk ID to its shortest unambiguous prefix.
+    pub fn short_ids(&self) -> HashMap<String, String> {
+        let mut ids: Vec<&str> = self.all_ids().collect();
+        ids.sort_unstable();
+        let shared =
+            |a: &str, b: &str| a.bytes().zip(b.bytes()).take_while(|(x, y)| x == y).count();
+        (0..ids.len())
+            .map(|i| {
+                let before = if i > 0 { shared(ids[i - 1], ids[i]) } else { 0 };
+                let after = ids.get(i + 1).map_or(0, |next| shared(ids[i], next));
+                let length = (before.max(after) + 1).max(MIN_SHORT_ID).min(ids[i].len());
+                (ids[i].to_string(), ids[i][..length].to_string())
+            })
+            .collect()
+    }
+
+    /// Finds the task or chunk whose ID starts with `prefix`, returning the
+    /// task's category and position and, for a chunk, its position.
+    pub fn find_id(&self, prefix: &str) -> Result<Option<(String, usize, Option<usize>)>, String> {
+        let prefix = prefix.to_lowercase();
+        if prefix.len() < MIN_SHORT_ID || !prefix.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Ok(None);
+        }
+        let mut found = Vec::new();
+        for (category, tasks) in &self.categorization.categories {
+            for (index, task) in tasks.iter().enumerate() {
+                if task.id.starts_with(&prefix) {
+                    found.push((category.clone(), index, None));
+                }
+                for (chunk, time_chunk) in task.time_chunks.iter().enumerate() {
+                    if time_chunk.id.starts_with(&prefix) {
+                        found.push((category.clone(), index, Some(chunk)));
+                    }
+                }
+            }
+        }
+        match found.len() {
+            0 | 1 => Ok(found.pop()),
+            n => Err(format!(
+                "{} IDs start with {:?}; use a longer prefix",
+                n, prefix
+            )),
+        }
+    }
+}
+
 impl Task {
-    pub fn new(name: &str) -> Task {
+    pub fn new(id: &str, name: &str, start_time: DateTime<Utc>, chunk_id: &str) -> Task {
         Task {
+            id: id.to_string(),
             name: name.to_string(),
+            tags: BTreeSet::new(),
+            notes: Vec::new(),
             time_chunks: vec![TimeChunk {
-                start_time: Utc::now(),
+                id: chunk_id.to_string(),
+                start_time,
                 end_time: None,
+                notes: Vec::new(),
             }],
+            pauses: Vec::new(),
             paused_duration: Duration::from_secs(0),
             status: TaskStatus::Running,
         }
     }
 
-    pub fn stop(&mut self) {
+    /// A stopped task made of already finished chunks.
+    pub fn from_chunks(name: &str, time_chunks: Vec<TimeChunk>) -> Task {
+        Task {
+            id: new_id(),
+            name: name.to_string(),
+            tags: BTreeSet::new(),
+            notes: Vec::new(),
+            time_chunks,
+            pauses: Vec::new(),
+            paused_duration: Duration::from_secs(0),
+            status: TaskStatus::Stopped,
+        }
+    }
+
+    /// The task's tags including its primary `category` and, for a nested
+    /// category like `#acme/website`, each level above it (`acme`). Without
+    /// `#`.
+    pub fn all_tags(&self, category: &str) -> BTreeSet<String> {
+        let mut tags = self.tags.clone();
+        if category.starts_with('#') {
+            let path = Categorization::category_path(category);
+            for depth in 1..=path.len() {
+                tags.insert(path[..depth].join("/"));
+            }
+        }
+        tags
+    }
+
+    /// Takes over `other`'s chunks and pauses, keeping them in time order
+    /// with any open chunk or pause last. At most one of the two tasks may
+    /// be running or paused.
+    pub fn absorb(&mut self, other: Task) {
+        let open_last = |chunk: &TimeChunk| (chunk.end_time.is_none(), chunk.start_time);
+        self.time_chunks.extend(other.time_chunks);
+        self.time_chunks.sort_by_key(open_last);
+        self.pauses.extend(other.pauses);
+        self.pauses.sort_by_key(open_last);
+        self.paused_duration += other.paused_duration;
+        self.tags.extend(other.tags);
+        self.notes.extend(other.notes);
+        self.notes.sort_by_key(|note| note.at);
+        if self.status == TaskStatus::Stopped {
+            self.status = other.status;
+        }
+    }
+
+    pub fn stop_at(&mut self, at: DateTime<Utc>) {
         if let Some(last_chunk) = self.time_chunks.last_mut() {
-            last_chunk.end_time = Some(Utc::now());
+            if last_chunk.end_time.is_none() {
+                last_chunk.end_time = Some(at);
+            }
         }
+        self.end_pause(at);
         self.status = TaskStatus::Stopped;
     }
 
-    pub fn pause(&mut self) {
+    pub fn pause_at(&mut self, at: DateTime<Utc>) {
         if let TaskStatus::Running = self.status {
             if let Some(last_chunk) = self.time_chunks.last_mut() {
-                last_chunk.end_time = Some(Utc::now());
+                last_chunk.end_time = Some(at);
             }
+            self.pauses.push(TimeChunk {
+                id: String::new(),
+                start_time: at,
+                end_time: None,
+                notes: Vec::new(),
+            });
             self.status = TaskStatus::Paused;
         }
     }
 
-    pub fn resume(&mut self) {
+    pub fn resume_at(&mut self, at: DateTime<Utc>, chunk_id: &str) {
         if let TaskStatus::Paused = self.status {
+            self.end_pause(at);
+            self.start_at(at, chunk_id);
+        }
+    }
+
+    /// Closes the open pause, if any, and brings `paused_duration` up to date.
+    fn end_pause(&mut self, at: DateTime<Utc>) {
+        if let Some(pause) = self.pauses.last_mut() {
+            if pause.end_time.is_none() {
+                pause.end_time = Some(at);
+            }
+        }
+        self.paused_duration = self
+            .pauses
+            .iter()
+            .filter_map(|pause| pause.end_time.map(|end| end - pause.start_time))
+            .fold(Duration::from_secs(0), |acc, paused| {
+                acc + paused.to_std().unwrap_or_default()
+            });
+    }
+
+    /// Sum of all pauses, counting an ongoing pause up to `now`.
+    pub fn paused(&self, now: DateTime<Utc>) -> chrono::Duration {
+        self.pauses
+            .iter()
+            .fold(chrono::Duration::zero(), |acc, pause| {
+                acc + pause.duration(now)
+            })
+    }
+
+    /// Sum of all chunks, counting a running chunk up to `now`.
+    pub fn tracked(&self, now: DateTime<Utc>) -> chrono::Duration {
+        self.time_chunks
+            .iter()
+            .fold(chrono::Duration::zero(), |acc, chunk| {
+                acc + chunk.duration(now)
+            })
+    }
+
+    /// Opens a new chunk unless the task is already running. Starting a
+    /// paused task ends its pause, just like resuming it.
+    pub fn start_at(&mut self, at: DateTime<Utc>, chunk_id: &str) {
+        if self.status == TaskStatus::Paused {
+            self.end_pause(at);
+        }
+        if self.status != TaskStatus::Running {
             self.time_chunks.push(TimeChunk {
-                start_time: Utc::now(),
+                id: chunk_id.to_string(),
+                start_time: at,
                 end_time: None,
+                notes: Vec::new(),
             });
             self.status = TaskStatus::Running;
         }
@@ -93,33 +412,330 @@ impl Task {
         let remaining_seconds = seconds % 60;
         println!("{:0>2}:{:0>2}:{:0>2}", hours, minutes, remaining_seconds,);
     }
-    pub fn chrono_duration(&self) -> chrono::Duration {
-        self.time_chunks
-            .iter()
-            .filter_map(|chunk| chunk.end_time.map(|t| t - chunk.start_time))
-            .fold(chrono::Duration::zero(), |acc, x| acc + x)
-            + (Utc::now() - self.time_chunks.last().unwrap().start_time)
+}
+
+#[derive(Debug)]
+pub enum DataError {
+    /// No data file exists yet at the storage location.
+    Missing(PathBuf),
+    Io(std::io::Error),
+    /// The data file could not be parsed and was moved aside.
+    Corrupt {
+        path: PathBuf,
+        quarantined: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The file was written by a newer (or unknown) schema version.
+    UnsupportedVersion {
+        path: PathBuf,
+        version: u32,
+    },
+    /// An older file could not be upgraded; it is left untouched.
+    Migration {
+        path: PathBuf,
+        version: u32,
+        message: String,
+    },
+    /// A corrupt file was quarantined and the user has not yet run `recover`.
+    RecoveryPending {
+        path: PathBuf,
+        quarantined: PathBuf,
+    },
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataError::Missing(path) => write!(f, "No data file at {:?}", path),
+            DataError::Io(e) => write!(f, "Could not access data file: {}", e),
+            DataError::Corrupt {
+                path,
+                quarantined,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Could not parse {:?} at line {}, column {}: {}\n\
+                 The file was moved to {:?} and nothing will be written until you run \
+                 `recover --restore <file>` with a repaired copy or `recover --start-fresh`.",
+                path, line, column, message, quarantined
+            ),
+            DataError::UnsupportedVersion { path, version } => write!(
+                f,
+                "{:?} uses schema version {}, but this build only understands up to {}",
+                path, version, SCHEMA_VERSION
+            ),
+            DataError::Migration {
+                path,
+                version,
+                message,
+            } => write!(
+                f,
+                "Could not upgrade {:?} from schema version {}: {}",
+                path, version, message
+            ),
+            DataError::RecoveryPending { path, quarantined } => write!(
+                f,
+                "{:?} is awaiting recovery (corrupt data was moved to {:?}). \
+                 Run `recover --restore <file>` or `recover --start-fresh` first.",
+                path, quarantined
+            ),
+        }
     }
 }
 
-pub fn load_data(path: &PathBuf) -> TimePeriod {
-    let file = File::open(path);
-    match file {
-        Ok(file) => {
-            let reader = BufReader::new(file);
-            serde_json::from_reader(reader).unwrap_or_else(|_| TimePeriod {
-                categorization: Categorization::new(),
-            })
+impl Error for DataError {}
+
+impl From<std::io::Error> for DataError {
+    fn from(e: std::io::Error) -> Self {
+        DataError::Io(e)
+    }
+}
+
+/// Version of the layout written by `save_data`. Bump it together with a new
+/// entry in `MIGRATIONS` whenever `TimePeriod` or anything it contains changes
+/// shape.
+pub const SCHEMA_VERSION: u32 = 6;
+
+type MigrationStep = fn(Value) -> Result<Value, String>;
+
+/// `MIGRATIONS[i]` upgrades a file written with schema version `i + 1`.
+const MIGRATIONS: &[MigrationStep] = &[
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+    migrate_v4_to_v5,
+    migrate_v5_to_v6,
+];
+
+/// Version 1 files predate the `version` field. Version 2 introduced only that
+/// field, which `save_data` writes, so there is nothing to convert; the step
+/// keeps `MIGRATIONS[i]` lined up with version `i + 1`.
+fn migrate_v1_to_v2(value: Value) -> Result<Value, String> {
+    Ok(value)
+}
+
+/// Version 3 records pause intervals. Older files never tracked pauses, so
+/// every task starts with none.
+fn migrate_v2_to_v3(mut value: Value) -> Result<Value, String> {
+    for_each_task(&mut value, |task| {
+        task.entry("pauses")
+            .or_insert_with(|| Value::Array(Vec::new()));
+    })?;
+    Ok(value)
+}
+
+/// Runs `change` on every task object of a data file.
+fn for_each_task(
+    value: &mut Value,
+    mut change: impl FnMut(&mut serde_json::Map<String, Value>),
+) -> Result<(), String> {
+    let categories = value
+        .pointer_mut("/categorization/categories")
+        .and_then(Value::as_object_mut)
+        .ok_or("missing categorization.categories")?;
+    for tasks in categories.values_mut() {
+        let tasks = tasks
+            .as_array_mut()
+            .ok_or("category is not a list of tasks")?;
+        for task in tasks {
+            change(task.as_object_mut().ok_or("task is not an object")?);
         }
-        Err(_) => TimePeriod {
-            categorization: Categorization::new(),
+    }
+    Ok(())
+}
+
+/// Version 4 gives every task and chunk an ID.
+fn migrate_v3_to_v4(value: Value) -> Result<Value, String> {
+    let mut time_period: TimePeriod = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    time_period.assign_missing_ids();
+    serde_json::to_value(time_period).map_err(|e| e.to_string())
+}
+
+/// Version 5 adds tags besides the primary category. Older tasks only ever
+/// had the category.
+fn migrate_v4_to_v5(mut value: Value) -> Result<Value, String> {
+    for_each_task(&mut value, |task| {
+        task.entry("tags")
+            .or_insert_with(|| Value::Array(Vec::new()));
+    })?;
+    Ok(value)
+}
+
+/// Version 6 adds notes. Chunks only store theirs when they have any.
+fn migrate_v5_to_v6(mut value: Value) -> Result<Value, String> {
+    for_each_task(&mut value, |task| {
+        task.entry("notes")
+            .or_insert_with(|| Value::Array(Vec::new()));
+    })?;
+    Ok(value)
+}
+
+#[derive(Serialize)]
+struct VersionedData<'a> {
+    version: u32,
+    #[serde(flatten)]
+    time_period: &'a TimePeriod,
+}
+
+/// Reads the data file, upgrading it in place when it uses an older schema.
+///
+/// The upgraded file is written back even when the command only reads. The
+/// event log is seeded from whatever this returns, so the file on disk has to
+/// agree with it, and upgrading only in memory would redo the migration on
+/// every run, status polls included. The caller holds the data lock, and the
+/// original file is kept as `<file>.v<version>.bak`.
+pub fn load_data(path: &PathBuf) -> Result<TimePeriod, DataError> {
+    check_recovery(path)?;
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Err(DataError::Missing(path.clone())),
+        Err(e) => return Err(e.into()),
+    };
+    let corrupt = |e: serde_json::Error| match quarantine(path) {
+        Ok(quarantined) => DataError::Corrupt {
+            path: path.clone(),
+            quarantined,
+            line: e.line(),
+            column: e.column(),
+            message: e.to_string(),
         },
+        Err(e) => e,
+    };
+
+    let value: Value = serde_json::from_str(&contents).map_err(corrupt)?;
+    let version = stored_version(&value);
+    if version == SCHEMA_VERSION {
+        return serde_json::from_str(&contents).map_err(corrupt);
+    }
+    if version > SCHEMA_VERSION || version == 0 {
+        return Err(DataError::UnsupportedVersion {
+            path: path.clone(),
+            version,
+        });
     }
+
+    let time_period = upgrade(value, version).map_err(|message| DataError::Migration {
+        path: path.clone(),
+        version,
+        message,
+    })?;
+
+    let backup = sibling_path(path, &format!("v{}.bak", version));
+    fs::write(&backup, &contents)?;
+    save_data(path, &time_period)?;
+    eprintln!(
+        "Upgraded {:?} from schema version {} to {} (previous file kept at {:?})",
+        path, version, SCHEMA_VERSION, backup
+    );
+    Ok(time_period)
 }
 
-pub fn save_data(path: &PathBuf, time_period: &TimePeriod) -> Result<(), Box<dyn Error>> {
-    let file = File::create(path)?;
-    let writer = BufWriter::new(file);
-    serde_json::to_writer(writer, time_period)?;
+/// Reads a data file of any supported schema version without touching it,
+/// e.g. a backup snapshot or a repaired copy handed to `recover`.
+pub fn read_data_file(path: &Path) -> Result<TimePeriod, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)?;
+    let value: Value = serde_json::from_str(&contents)
+        .map_err(|e| format!("{:?} is not valid JSON: {}", path, e))?;
+    let version = stored_version(&value);
+    if version == SCHEMA_VERSION {
+        return serde_json::from_str(&contents)
+            .map_err(|e| format!("{:?} is not a valid data file: {}", path, e).into());
+    }
+    if version > SCHEMA_VERSION || version == 0 {
+        return Err(DataError::UnsupportedVersion {
+            path: path.to_path_buf(),
+            version,
+        }
+        .into());
+    }
+    upgrade(value, version).map_err(|message| {
+        DataError::Migration {
+            path: path.to_path_buf(),
+            version,
+            message,
+        }
+        .into()
+    })
+}
+
+fn stored_version(value: &Value) -> u32 {
+    value
+        .get("version")
+        .and_then(Value::as_u64)
+        .map_or(1, |version| version as u32)
+}
+
+/// Runs every migration from `version` up to `SCHEMA_VERSION`.
+fn upgrade(value: Value, version: u32) -> Result<TimePeriod, String> {
+    let migrated = MIGRATIONS[(version - 1) as usize..]
+        .iter()
+        .try_fold(value, |value, migrate| migrate(value))?;
+    serde_json::from_value(migrated).map_err(|e| e.to_string())
+}
+
+/// Moves a corrupt data file aside under a timestamped name and leaves a
+/// marker that blocks writes until the user confirms recovery.
+fn quarantine(path: &Path) -> Result<PathBuf, DataError> {
+    let timestamp = Utc::now().format("%Y%m%dT%H%M%S");
+    let quarantined = sibling_path(path, &format!("corrupt-{}", timestamp));
+    fs::rename(path, &quarantined)?;
+    fs::write(
+        sibling_path(path, "recovery"),
+        quarantined.to_string_lossy().as_bytes(),
+    )?;
+    Ok(quarantined)
+}
+
+fn check_recovery(path: &Path) -> Result<(), DataError> {
+    match fs::read_to_string(sibling_path(path, "recovery")) {
+        Ok(quarantined) => Err(DataError::RecoveryPending {
+            path: path.to_path_buf(),
+            quarantined: PathBuf::from(quarantined),
+        }),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Whether a corrupt data file was quarantined and `recover` has not run yet.
+pub fn recovery_pending(path: &Path) -> bool {
+    sibling_path(path, "recovery").exists()
+}
+
+/// Lets writes to `path` through again after a quarantine.
+pub fn clear_recovery(path: &Path) -> Result<(), DataError> {
+    match fs::remove_file(sibling_path(path, "recovery")) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Writes to a sibling temp file and renames it over `path`, so a crash
+/// mid-write leaves the previous data intact.
+pub fn save_data(path: &PathBuf, time_period: &TimePeriod) -> Result<(), DataError> {
+    check_recovery(path)?;
+    let tmp_path = sibling_path(path, "tmp");
+    let file = File::create(&tmp_path)?;
+    let mut writer = BufWriter::new(file);
+    let data = VersionedData {
+        version: SCHEMA_VERSION,
+        time_period,
+    };
+    serde_json::to_writer(&mut writer, &data).map_err(std::io::Error::from)?;
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    fs::rename(&tmp_path, path)?;
     Ok(())
 }
+
+/// `tasks.json` -> `tasks.json.<suffix>`
+pub fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
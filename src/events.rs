@@ -0,0 +1,626 @@
+// src/events.rs
+use crate::categorization::Categorization;
+use crate::data::{DataError, Task, TaskStatus, TimePeriod};
+use crate::storage::Storage;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+/// One immutable entry in the append-only log. The current `TimePeriod` is
+/// whatever replaying every event in order produces.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Event {
+    pub id: u64,
+    pub timestamp: DateTime<Utc>,
+    pub user: String,
+    pub action: Action,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum Action {
+    /// `description` is the task text as typed, including its `#category`.
+    /// `task_id` is only used if this creates the task. Events logged before
+    /// IDs existed have neither ID, and events logged before tags existed
+    /// have no `tags` and read the description the old way.
+    Start {
+        description: String,
+        at: DateTime<Utc>,
+        #[serde(default)]
+        task_id: String,
+        #[serde(default)]
+        chunk_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tags: Option<BTreeSet<String>>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        displaced: Vec<Displaced>,
+    },
+    Stop {
+        category: String,
+        task: String,
+        at: DateTime<Utc>,
+    },
+    Pause {
+        category: String,
+        task: String,
+        at: DateTime<Utc>,
+    },
+    Resume {
+        category: String,
+        task: String,
+        at: DateTime<Utc>,
+        #[serde(default)]
+        chunk_id: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        displaced: Vec<Displaced>,
+    },
+    /// Creates (`before` is empty), replaces or deletes (`after` is empty)
+    /// whole tasks in one step.
+    Edit {
+        changes: Vec<TaskChange>,
+    },
+    Clear,
+    /// Replaces everything, e.g. when seeding the log from an existing data
+    /// file or restoring a backup.
+    Snapshot {
+        categorization: Categorization,
+    },
+    /// Takes back the event `target`; replay skips it from then on.
+    Undo {
+        target: u64,
+    },
+    /// Brings back an undone event.
+    Redo {
+        target: u64,
+    },
+}
+
+/// A running task that a `Start` or `Resume` stopped or paused at the same
+/// moment, kept on that event so undoing it brings the task back.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Displaced {
+    pub category: String,
+    pub task: String,
+    /// Paused rather than stopped.
+    #[serde(default)]
+    pub paused: bool,
+}
+
+impl Displaced {
+    /// The `Stop` or `Pause` this amounts to at `at`.
+    pub fn action(&self, at: DateTime<Utc>) -> Action {
+        let (category, task) = (self.category.clone(), self.task.clone());
+        if self.paused {
+            Action::Pause { category, task, at }
+        } else {
+            Action::Stop { category, task, at }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskChange {
+    pub category: String,
+    pub before: Option<Task>,
+    pub after: Option<Task>,
+}
+
+/// What an applied action touched, so storage can write just those rows.
+pub enum Touched {
+    Tasks(Vec<(String, usize)>),
+    Everything,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Start {
+                description,
+                displaced,
+                ..
+            } => {
+                write!(f, "start {:?}", description)?;
+                describe_displaced(f, displaced)
+            }
+            Action::Stop { category, task, .. } => write!(f, "stop {:?} ({})", task, category),
+            Action::Pause { category, task, .. } => write!(f, "pause {:?} ({})", task, category),
+            Action::Resume {
+                category,
+                task,
+                displaced,
+                ..
+            } => {
+                write!(f, "resume {:?} ({})", task, category)?;
+                describe_displaced(f, displaced)
+            }
+            Action::Edit { changes } => write!(f, "{}", describe_changes(changes).join(", ")),
+            Action::Clear => write!(f, "clear"),
+            Action::Undo { target } => write!(f, "undo #{}", target),
+            Action::Redo { target } => write!(f, "redo #{}", target),
+            Action::Snapshot { categorization } => write!(
+                f,
+                "load snapshot of {} tasks",
+                categorization
+                    .categories
+                    .values()
+                    .map(Vec::len)
+                    .sum::<usize>()
+            ),
+        }
+    }
+}
+
+fn describe_displaced(f: &mut fmt::Formatter<'_>, displaced: &[Displaced]) -> fmt::Result {
+    for other in displaced {
+        let verb = if other.paused { "pausing" } else { "stopping" };
+        write!(f, ", {} {:?} ({})", verb, other.task, other.category)?;
+    }
+    Ok(())
+}
+
+/// A task deleted from one category and added to another with the same ID
+/// reads as a move.
+fn describe_changes(changes: &[TaskChange]) -> Vec<String> {
+    let added_to = |id: &str| {
+        changes
+            .iter()
+            .find_map(|change| match (&change.before, &change.after) {
+                (None, Some(after)) if !id.is_empty() && after.id == id => Some(&change.category),
+                _ => None,
+            })
+    };
+    let removed = |id: &str| {
+        changes
+            .iter()
+            .any(|change| match (&change.before, &change.after) {
+                (Some(before), None) => !id.is_empty() && before.id == id,
+                _ => false,
+            })
+    };
+    changes
+        .iter()
+        .filter_map(|change| match (&change.before, &change.after) {
+            (None, Some(after)) if removed(&after.id) => None,
+            (None, Some(after)) => Some(format!("add {:?}", after.name)),
+            (Some(before), None) => Some(match added_to(&before.id) {
+                Some(to) => format!("move {:?} from {} to {}", before.name, change.category, to),
+                None => format!("delete {:?}", before.name),
+            }),
+            (Some(before), Some(after)) if before.name != after.name => {
+                Some(format!("rename {:?} to {:?}", before.name, after.name))
+            }
+            (Some(before), Some(_)) => Some(format!("edit {:?}", before.name)),
+            (None, None) => None,
+        })
+        .collect()
+}
+
+fn find_task<'a>(
+    time_period: &'a mut TimePeriod,
+    category: &str,
+    name: &str,
+    status: Option<TaskStatus>,
+) -> Option<(usize, &'a mut Task)> {
+    time_period
+        .categorization
+        .categories
+        .get_mut(category)?
+        .iter_mut()
+        .enumerate()
+        .find(|(_, task)| task.name == name && status.as_ref().is_none_or(|s| *s == task.status))
+}
+
+/// Applies one action to `time_period`. Actions that refer to a task that no
+/// longer exists are ignored, so replaying never fails.
+pub fn apply(time_period: &mut TimePeriod, action: &Action) -> Touched {
+    let displaced = match action {
+        Action::Start { displaced, at, .. } | Action::Resume { displaced, at, .. } => {
+            displaced.iter().map(|other| other.action(*at)).collect()
+        }
+        _ => Vec::new(),
+    };
+    let mut touched = Vec::new();
+    for action in &displaced {
+        if let Touched::Tasks(tasks) = apply_one(time_period, action) {
+            touched.extend(tasks);
+        }
+    }
+    match apply_one(time_period, action) {
+        // The task the action is about comes first.
+        Touched::Tasks(mut tasks) => {
+            tasks.extend(touched);
+            Touched::Tasks(tasks)
+        }
+        Touched::Everything => Touched::Everything,
+    }
+}
+
+fn apply_one(time_period: &mut TimePeriod, action: &Action) -> Touched {
+    let touched =
+        |category: &str, index: usize| Touched::Tasks(vec![(category.to_string(), index)]);
+    match action {
+        Action::Start {
+            description,
+            at,
+            task_id,
+            chunk_id,
+            tags,
+            ..
+        } => {
+            let (category, task, tags) = match tags {
+                Some(tags) => {
+                    let (category, task, _) = Categorization::parse_description(description);
+                    (category, task, tags.clone())
+                }
+                None => {
+                    let (category, task) =
+                        Categorization::extract_category_from_description(description);
+                    (category, task, BTreeSet::new())
+                }
+            };
+            if let Some((index, existing)) = find_task(time_period, &category, &task, None) {
+                existing.start_at(*at, chunk_id);
+                existing.tags.extend(tags);
+                return touched(&category, index);
+            }
+            let mut new_task = Task::new(task_id, &task, *at, chunk_id);
+            new_task.tags = tags;
+            let tasks = time_period
+                .categorization
+                .categories
+                .entry(category.clone())
+                .or_default();
+            tasks.push(new_task);
+            touched(&category, tasks.len() - 1)
+        }
+        Action::Stop { category, task, at } => {
+            // Both running and paused tasks can be stopped.
+            let tasks = time_period.categorization.categories.get_mut(category);
+            match tasks.and_then(|tasks| {
+                tasks.iter_mut().enumerate().find(|(_, existing)| {
+                    existing.name == *task && existing.status != TaskStatus::Stopped
+                })
+            }) {
+                Some((index, existing)) => {
+                    existing.stop_at(*at);
+                    touched(category, index)
+                }
+                None => Touched::Tasks(Vec::new()),
+            }
+        }
+        Action::Pause { category, task, at } => {
+            match find_task(time_period, category, task, Some(TaskStatus::Running)) {
+                Some((index, existing)) => {
+                    existing.pause_at(*at);
+                    touched(category, index)
+                }
+                None => Touched::Tasks(Vec::new()),
+            }
+        }
+        Action::Resume {
+            category,
+            task,
+            at,
+            chunk_id,
+            ..
+        } => match find_task(time_period, category, task, Some(TaskStatus::Paused)) {
+            Some((index, existing)) => {
+                existing.resume_at(*at, chunk_id);
+                touched(category, index)
+            }
+            None => Touched::Tasks(Vec::new()),
+        },
+        Action::Edit { changes } => {
+            for change in changes {
+                let categories = &mut time_period.categorization.categories;
+                let tasks = categories.entry(change.category.clone()).or_default();
+                // Tasks replayed from before IDs existed only have a name.
+                let position = change.before.as_ref().and_then(|before| {
+                    tasks
+                        .iter()
+                        .position(|task| !before.id.is_empty() && task.id == before.id)
+                        .or_else(|| tasks.iter().position(|task| task.name == before.name))
+                });
+                match (position, &change.after) {
+                    (Some(index), Some(after)) => tasks[index] = after.clone(),
+                    (Some(index), None) => {
+                        tasks.remove(index);
+                    }
+                    (None, Some(after)) => tasks.push(after.clone()),
+                    (None, None) => {}
+                }
+                if tasks.is_empty() {
+                    categories.remove(&change.category);
+                }
+            }
+            Touched::Everything
+        }
+        Action::Clear => {
+            time_period.categorization = Categorization::new();
+            Touched::Everything
+        }
+        Action::Snapshot { categorization } => {
+            time_period.categorization = categorization.clone();
+            Touched::Everything
+        }
+        // Undo and redo change which events take part in a replay rather
+        // than the data itself; see `replay`.
+        Action::Undo { .. } | Action::Redo { .. } => Touched::Everything,
+    }
+}
+
+/// Walks the log like an editor's undo stack: returns the ids of the events
+/// currently in effect and of the undone ones `redo` can bring back, both
+/// oldest first. A new change after an undo discards the redo stack.
+fn undo_stacks(events: &[Event]) -> (Vec<u64>, Vec<u64>) {
+    let mut done: Vec<u64> = Vec::new();
+    let mut undone: Vec<u64> = Vec::new();
+    for event in events {
+        match event.action {
+            Action::Undo { target } => {
+                if done.last() == Some(&target) {
+                    done.pop();
+                    undone.push(target);
+                }
+            }
+            Action::Redo { target } => {
+                if undone.last() == Some(&target) {
+                    undone.pop();
+                    done.push(target);
+                }
+            }
+            _ => {
+                done.push(event.id);
+                undone.clear();
+            }
+        }
+    }
+    (done, undone)
+}
+
+/// The log's first snapshot holds the data that predates the log, so undo
+/// never goes past it.
+fn is_baseline(events: &[Event], id: u64) -> bool {
+    events
+        .first()
+        .is_some_and(|first| first.id == id && matches!(first.action, Action::Snapshot { .. }))
+}
+
+/// Rebuilds the data by replaying `events` from an empty state, skipping
+/// anything that has been undone.
+pub fn replay(events: &[Event]) -> TimePeriod {
+    let (done, _) = undo_stacks(events);
+    let done: HashSet<u64> = done.into_iter().collect();
+    let mut time_period = TimePeriod {
+        categorization: Categorization::new(),
+    };
+    for event in events.iter().filter(|event| done.contains(&event.id)) {
+        apply(&mut time_period, &event.action);
+    }
+    time_period.assign_missing_ids();
+    time_period
+}
+
+/// How one operation changed the data.
+pub enum Effect {
+    Task {
+        category: String,
+        before: Option<Box<Task>>,
+        after: Option<Box<Task>>,
+    },
+    TaskCount {
+        before: usize,
+        after: usize,
+    },
+    /// The operation is currently undone, or changed nothing.
+    Nothing,
+}
+
+pub struct HistoryEntry<'a> {
+    pub event: &'a Event,
+    pub undone: bool,
+    pub effect: Effect,
+}
+
+/// The last `limit` operations (undo and redo themselves excluded) with what
+/// each did to the task it touched.
+pub fn history(events: &[Event], limit: usize) -> Vec<HistoryEntry<'_>> {
+    let (done, _) = undo_stacks(events);
+    let done: HashSet<u64> = done.into_iter().collect();
+    let operations: Vec<&Event> = events
+        .iter()
+        .filter(|event| !matches!(event.action, Action::Undo { .. } | Action::Redo { .. }))
+        .collect();
+    let window_start = operations.len().saturating_sub(limit);
+
+    let mut time_period = TimePeriod {
+        categorization: Categorization::new(),
+    };
+    let mut entries = Vec::new();
+    for (position, event) in operations.into_iter().enumerate() {
+        let undone = !done.contains(&event.id);
+        if position < window_start {
+            if !undone {
+                apply(&mut time_period, &event.action);
+            }
+            continue;
+        }
+        if undone {
+            entries.push(HistoryEntry {
+                event,
+                undone,
+                effect: Effect::Nothing,
+            });
+            continue;
+        }
+
+        let previous = time_period.clone();
+        let effect = match apply(&mut time_period, &event.action) {
+            Touched::Tasks(tasks) => match tasks.into_iter().next() {
+                Some((category, index)) => Effect::Task {
+                    before: previous
+                        .categorization
+                        .categories
+                        .get(&category)
+                        .and_then(|tasks| tasks.get(index))
+                        .cloned()
+                        .map(Box::new),
+                    after: time_period.categorization.categories[&category]
+                        .get(index)
+                        .cloned()
+                        .map(Box::new),
+                    category,
+                },
+                None => Effect::Nothing,
+            },
+            Touched::Everything => Effect::TaskCount {
+                before: previous.task_count(),
+                after: time_period.task_count(),
+            },
+        };
+        entries.push(HistoryEntry {
+            event,
+            undone,
+            effect,
+        });
+    }
+    entries
+}
+
+/// Where the data file went when `load` failed because it is corrupt or
+/// awaiting recovery, rather than for a reason rewriting it would make worse.
+fn quarantined<'e>(error: &'e (dyn Error + 'static)) -> Option<&'e Path> {
+    match error.downcast_ref::<DataError>()? {
+        DataError::Corrupt { quarantined, .. } | DataError::RecoveryPending { quarantined, .. } => {
+            Some(quarantined)
+        }
+        _ => None,
+    }
+}
+
+pub fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| String::from("unknown"))
+}
+
+/// Records actions in the log and keeps storage's copy of the current data
+/// in step with it.
+pub struct Journal<'a> {
+    storage: &'a mut dyn Storage,
+    events: Vec<Event>,
+}
+
+impl<'a> Journal<'a> {
+    /// Loads the log and replays it. The log takes precedence: the data file
+    /// only mirrors it, so changes must go through `record`, and a data file
+    /// that is corrupt is rewritten from the log. Only a data file that
+    /// predates the log is read for its contents, and recorded as the log's
+    /// first snapshot.
+    pub fn open(storage: &'a mut dyn Storage) -> Result<(Self, TimePeriod), Box<dyn Error>> {
+        let events = storage.load_events()?;
+        let stored = match storage.load() {
+            Ok(stored) => stored,
+            Err(e) => match quarantined(e.as_ref()) {
+                Some(moved_to) if !events.is_empty() => {
+                    let time_period = replay(&events);
+                    storage.rebuild(&time_period)?;
+                    eprintln!(
+                        "The data file could not be read and was moved to {:?}; rebuilt it from the event log.",
+                        moved_to
+                    );
+                    return Ok((Journal { storage, events }, time_period));
+                }
+                _ => return Err(e),
+            },
+        };
+        let mut journal = Journal { storage, events };
+
+        if journal.events.is_empty() {
+            let mut time_period = TimePeriod {
+                categorization: Categorization::new(),
+            };
+            if !stored.categorization.categories.is_empty() {
+                journal.record(
+                    &mut time_period,
+                    Action::Snapshot {
+                        categorization: stored.categorization,
+                    },
+                )?;
+            }
+            return Ok((journal, time_period));
+        }
+
+        let time_period = replay(&journal.events);
+        Ok((journal, time_period))
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Undoes the most recent change still in effect, returning it.
+    pub fn undo(&mut self, time_period: &mut TimePeriod) -> Result<Option<Event>, Box<dyn Error>> {
+        let (done, _) = undo_stacks(&self.events);
+        let target = match done.last() {
+            Some(&target) if !is_baseline(&self.events, target) => target,
+            _ => return Ok(None),
+        };
+        self.record(time_period, Action::Undo { target })?;
+        Ok(self.event(target).cloned())
+    }
+
+    /// Redoes the most recently undone change, returning it.
+    pub fn redo(&mut self, time_period: &mut TimePeriod) -> Result<Option<Event>, Box<dyn Error>> {
+        let (_, undone) = undo_stacks(&self.events);
+        let target = match undone.last() {
+            Some(&target) => target,
+            None => return Ok(None),
+        };
+        self.record(time_period, Action::Redo { target })?;
+        Ok(self.event(target).cloned())
+    }
+
+    fn event(&self, id: u64) -> Option<&Event> {
+        self.events.iter().find(|event| event.id == id)
+    }
+
+    pub fn record(
+        &mut self,
+        time_period: &mut TimePeriod,
+        action: Action,
+    ) -> Result<(), Box<dyn Error>> {
+        let event = Event {
+            id: self.events.last().map_or(1, |event| event.id + 1),
+            timestamp: Utc::now(),
+            user: current_user(),
+            action,
+        };
+        self.storage.append_event(&event)?;
+        let touched = match event.action {
+            Action::Undo { .. } | Action::Redo { .. } => {
+                self.events.push(event);
+                *time_period = replay(&self.events);
+                Touched::Everything
+            }
+            _ => {
+                let touched = apply(time_period, &event.action);
+                self.events.push(event);
+                touched
+            }
+        };
+
+        match touched {
+            Touched::Everything => self.storage.save(time_period),
+            Touched::Tasks(tasks) => {
+                for (category, index) in tasks {
+                    self.storage.save_task(time_period, &category, index)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,397 @@
+// src/export.rs
+use crate::categorization::Categorization;
+use crate::data::{Note, Task, TimeChunk, TimePeriod};
+use crate::integration::TIMEWARRIOR_TIME;
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use serde::{Serialize, Serializer};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Column order of every export. Scripts rely on it, so only ever append.
+pub const COLUMNS: [&str; 8] = [
+    "task_id", "name", "category", "tags", "start", "end", "duration", "notes",
+];
+
+#[derive(Debug, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Ndjson,
+    /// Timewarrior's interval lines.
+    Timewarrior,
+    /// Watson's `frames` file.
+    Watson,
+    /// An iCalendar file with one event per chunk.
+    Ics,
+    /// Ledger's and hledger's timeclock `i`/`o` lines.
+    Timeclock,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            "ndjson" | "jsonl" => Ok(ExportFormat::Ndjson),
+            "timewarrior" | "timew" => Ok(ExportFormat::Timewarrior),
+            "watson" => Ok(ExportFormat::Watson),
+            "ics" | "ical" | "icalendar" => Ok(ExportFormat::Ics),
+            "timeclock" | "ledger" | "hledger" => Ok(ExportFormat::Timeclock),
+            other => Err(format!(
+                "Unknown export format {:?}; use csv, json, ndjson, timewarrior, watson, ics or timeclock",
+                other
+            )),
+        }
+    }
+}
+
+/// Which chunks to export: those starting in `[from, to)` of tasks in
+/// `category` or a category nested in it.
+#[derive(Default)]
+pub struct ExportFilter {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub category: Option<String>,
+}
+
+impl ExportFilter {
+    fn accepts(&self, category: &str, chunk: &TimeChunk) -> bool {
+        self.from.is_none_or(|from| chunk.start_time >= from)
+            && self.to.is_none_or(|to| chunk.start_time < to)
+            && self
+                .category
+                .as_deref()
+                .is_none_or(|ancestor| Categorization::is_within(category, ancestor))
+    }
+}
+
+/// One exported chunk, fields in `COLUMNS` order.
+#[derive(Serialize)]
+pub struct Row {
+    pub task_id: String,
+    pub name: String,
+    pub category: String,
+    pub tags: Vec<String>,
+    #[serde(serialize_with = "serialize_time")]
+    pub start: DateTime<Utc>,
+    /// Empty while the chunk is still running.
+    #[serde(serialize_with = "serialize_end")]
+    pub end: Option<DateTime<Utc>>,
+    /// Whole seconds, counting a running chunk up to the time of export.
+    pub duration: i64,
+    /// The chunk's own notes, then the task's notes written while it was the
+    /// task's latest chunk.
+    pub notes: Vec<String>,
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn serialize_time<S: Serializer>(at: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&timestamp(*at))
+}
+
+fn serialize_end<S: Serializer>(
+    end: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match end {
+        Some(end) => serialize_time(end, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl Row {
+    fn new(category: &str, task: &Task, chunk: &TimeChunk, now: DateTime<Utc>) -> Self {
+        // A task note belongs to the last chunk started by the time it was
+        // written, or to the first chunk if it predates them all.
+        let owner = |note: &Note| {
+            task.time_chunks
+                .iter()
+                .filter(|chunk| chunk.start_time <= note.at)
+                .max_by_key(|chunk| chunk.start_time)
+                .or_else(|| task.time_chunks.iter().min_by_key(|chunk| chunk.start_time))
+                .map(|owner| owner.id == chunk.id)
+                .unwrap_or(false)
+        };
+        let task_notes = task.notes.iter().filter(|note| owner(note));
+        Row {
+            task_id: task.id.clone(),
+            name: task.name.clone(),
+            category: category.to_string(),
+            tags: task.tags.iter().cloned().collect(),
+            start: chunk.start_time,
+            end: chunk.end_time,
+            duration: chunk.duration(now).num_seconds(),
+            notes: chunk
+                .notes
+                .iter()
+                .chain(task_notes)
+                .map(|note| note.text.clone())
+                .collect(),
+        }
+    }
+
+    /// The row as CSV fields: tags are separated by `;`, notes by ` | `.
+    fn fields(&self) -> [String; 8] {
+        [
+            self.task_id.clone(),
+            self.name.clone(),
+            self.category.clone(),
+            self.tags.join(";"),
+            timestamp(self.start),
+            self.end.map(timestamp).unwrap_or_default(),
+            self.duration.to_string(),
+            self.notes.join(" | "),
+        ]
+    }
+}
+
+/// Every chunk `filter` accepts, in the order they started.
+pub fn rows(time_period: &TimePeriod, filter: &ExportFilter, now: DateTime<Utc>) -> Vec<Row> {
+    let mut chunks: Vec<(&String, &Task, &TimeChunk)> = time_period
+        .categorization
+        .categories
+        .iter()
+        .flat_map(|(category, tasks)| {
+            tasks.iter().flat_map(move |task| {
+                task.time_chunks
+                    .iter()
+                    .map(move |chunk| (category, task, chunk))
+            })
+        })
+        .filter(|(category, _, chunk)| filter.accepts(category, chunk))
+        .collect();
+    chunks.sort_by(|a, b| (a.2.start_time, &a.1.id).cmp(&(b.2.start_time, &b.1.id)));
+    chunks
+        .into_iter()
+        .map(|(category, task, chunk)| Row::new(category, task, chunk, now))
+        .collect()
+}
+
+pub fn write_rows(
+    rows: &[Row],
+    format: ExportFormat,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(COLUMNS)?;
+            for row in rows {
+                writer.write_record(row.fields())?;
+            }
+            writer.flush()?;
+        }
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, rows)?;
+            writeln!(out)?;
+        }
+        ExportFormat::Ndjson => {
+            for row in rows {
+                serde_json::to_writer(&mut *out, row)?;
+                writeln!(out)?;
+            }
+        }
+        ExportFormat::Timewarrior => {
+            for row in rows {
+                writeln!(out, "{}", timewarrior_line(row))?;
+            }
+        }
+        ExportFormat::Watson => {
+            let frames: Vec<serde_json::Value> = rows.iter().filter_map(watson_frame).collect();
+            serde_json::to_writer_pretty(&mut *out, &frames)?;
+            writeln!(out)?;
+        }
+        ExportFormat::Ics => {
+            for line in calendar_lines(rows) {
+                write!(out, "{}\r\n", fold(&line))?;
+            }
+        }
+        ExportFormat::Timeclock => {
+            for line in timeclock_lines(rows) {
+                writeln!(out, "{}", line)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes Timewarrior's layout into `dir`: one `YYYY-MM.data` file per month
+/// the intervals start in. Returns how many files were written.
+pub fn write_timewarrior_dir(rows: &[Row], dir: &Path) -> Result<usize, Box<dyn Error>> {
+    let mut months: BTreeMap<String, Vec<&Row>> = BTreeMap::new();
+    for row in rows {
+        months
+            .entry(row.start.format("%Y-%m").to_string())
+            .or_default()
+            .push(row);
+    }
+    for (month, rows) in &months {
+        let mut file = File::create(dir.join(format!("{}.data", month)))?;
+        for row in rows {
+            writeln!(file, "{}", timewarrior_line(row))?;
+        }
+    }
+    Ok(months.len())
+}
+
+/// `inc <start> [- <end>] # <name> [#category] <tags> [# "<notes>"]`: the
+/// task name comes first and the category is the only `#tag`, which is how
+/// `tt import timewarrior` reads them back.
+fn timewarrior_line(row: &Row) -> String {
+    let mut line = format!("inc {}", row.start.format(TIMEWARRIOR_TIME));
+    if let Some(end) = row.end {
+        line.push_str(&format!(" - {}", end.format(TIMEWARRIOR_TIME)));
+    }
+    let mut tags = vec![quote(&row.name, false)];
+    if row.category != "Uncategorized" {
+        tags.push(quote(&row.category, false));
+    }
+    tags.extend(row.tags.iter().map(|tag| quote(tag, false)));
+    line.push_str(" # ");
+    line.push_str(&tags.join(" "));
+    if !row.notes.is_empty() {
+        line.push_str(" # ");
+        line.push_str(&quote(&row.notes.join("; "), true));
+    }
+    line
+}
+
+/// Quotes a Timewarrior word when it would not survive as a bare one.
+fn quote(word: &str, always: bool) -> String {
+    if always
+        || word.is_empty()
+        || word == "#"
+        || word.contains(|c: char| c.is_whitespace() || c == '"')
+    {
+        format!("\"{}\"", word.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        word.to_string()
+    }
+}
+
+/// `[start, stop, project, id, tags, updated_at]`. Watson has no task names,
+/// so the name is the first tag and the category path is the project.
+/// Running chunks are left out, as Watson keeps those elsewhere.
+fn watson_frame(row: &Row) -> Option<serde_json::Value> {
+    let end = row.end?;
+    let project = row.category.strip_prefix('#').unwrap_or(&row.category);
+    let mut tags = vec![row.name.clone()];
+    tags.extend(row.tags.iter().cloned());
+    Some(json!([
+        row.start.timestamp(),
+        end.timestamp(),
+        project,
+        format!("{}{:016x}", row.task_id, row.start.timestamp()),
+        tags,
+        end.timestamp(),
+    ]))
+}
+
+/// A calendar with one VEVENT per finished chunk: the task name as summary,
+/// the category and tags as categories, and the notes as description. The
+/// time stamp is the chunk's end rather than the time of export, so
+/// exporting twice gives the same file.
+fn calendar_lines(rows: &[Row]) -> Vec<String> {
+    const TIME: &str = "%Y%m%dT%H%M%SZ";
+    let mut lines: Vec<String> = [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//time_tracker//tt//EN",
+    ]
+    .map(String::from)
+    .into();
+    for row in rows {
+        let Some(end) = row.end else {
+            continue;
+        };
+        lines.push(String::from("BEGIN:VEVENT"));
+        lines.push(format!(
+            "UID:{}-{}@time_tracker",
+            row.task_id,
+            row.start.timestamp()
+        ));
+        lines.push(format!("DTSTAMP:{}", end.format(TIME)));
+        lines.push(format!("DTSTART:{}", row.start.format(TIME)));
+        lines.push(format!("DTEND:{}", end.format(TIME)));
+        lines.push(format!("SUMMARY:{}", calendar_escape(&row.name)));
+        let categories: Vec<String> = Some(&row.category)
+            .filter(|category| *category != "Uncategorized")
+            .into_iter()
+            .chain(&row.tags)
+            .map(|category| calendar_escape(category))
+            .collect();
+        if !categories.is_empty() {
+            lines.push(format!("CATEGORIES:{}", categories.join(",")));
+        }
+        if !row.notes.is_empty() {
+            lines.push(format!(
+                "DESCRIPTION:{}",
+                calendar_escape(&row.notes.join("\n"))
+            ));
+        }
+        lines.push(String::from("END:VEVENT"));
+    }
+    lines.push(String::from("END:VCALENDAR"));
+    lines
+}
+
+fn calendar_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Calendar lines longer than 75 bytes go on after a line break and a space.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
+
+/// `i <date> <time> <account>  <task name>` and `o <date> <time>` for each
+/// chunk, in local time, with each note as an indented `; <note>` comment
+/// between them. The account is the category path, `#acme/web` becoming
+/// `acme:web`. A running chunk only gets its `i` line, which hledger counts
+/// up to now, so those go last.
+fn timeclock_lines(rows: &[Row]) -> Vec<String> {
+    let time = |at: DateTime<Utc>| at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S");
+    let mut lines = Vec::new();
+    let (finished, running): (Vec<&Row>, Vec<&Row>) =
+        rows.iter().partition(|row| row.end.is_some());
+    for row in finished.into_iter().chain(running) {
+        let account = row
+            .category
+            .strip_prefix('#')
+            .unwrap_or(&row.category)
+            .replace('/', ":");
+        let name = row.name.split_whitespace().collect::<Vec<_>>().join(" ");
+        lines.push(format!("i {} {}  {}", time(row.start), account, name));
+        for note in &row.notes {
+            let note = note.split_whitespace().collect::<Vec<_>>().join(" ");
+            lines.push(format!("    ; {}", note));
+        }
+        if let Some(end) = row.end {
+            lines.push(format!("o {}", time(end)));
+        }
+    }
+    lines
+}
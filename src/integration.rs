@@ -0,0 +1,1055 @@
+// src/integration.rs
+use crate::utils;
+use chrono::{
+    DateTime, Datelike, Days, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Another tracker whose data `tt import` reads: the CSV export of a hosted
+/// tracker, or the files of a command-line one.
+#[derive(Debug, Clone, Copy)]
+pub enum ImportSource {
+    Toggl,
+    Clockify,
+    Harvest,
+    Timewarrior,
+    Watson,
+    /// An iCalendar file, e.g. a calendar exported with its meetings.
+    Ics,
+    /// Ledger's and hledger's timeclock `i`/`o` lines.
+    Timeclock,
+}
+
+impl FromStr for ImportSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "toggl" => Ok(ImportSource::Toggl),
+            "clockify" => Ok(ImportSource::Clockify),
+            "harvest" => Ok(ImportSource::Harvest),
+            "timewarrior" | "timew" => Ok(ImportSource::Timewarrior),
+            "watson" => Ok(ImportSource::Watson),
+            "ics" | "ical" | "icalendar" => Ok(ImportSource::Ics),
+            "timeclock" | "ledger" | "hledger" => Ok(ImportSource::Timeclock),
+            other => Err(format!(
+                "Unknown import source {:?}; use toggl, clockify, harvest, timewarrior, watson, ics or timeclock",
+                other
+            )),
+        }
+    }
+}
+
+/// One block of time read from another tracker, mapped onto our model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub category: String,
+    pub name: String,
+    pub tags: BTreeSet<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub notes: Vec<String>,
+}
+
+/// What `tt import` keeps of what it read, and where entries that come
+/// without a category go.
+#[derive(Default)]
+pub struct Rules {
+    /// Keep only entries mentioning one of these, if any are given.
+    pub include: Vec<String>,
+    /// Drop entries mentioning any of these.
+    pub exclude: Vec<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub category: Option<String>,
+}
+
+impl Rules {
+    pub fn apply(&self, entries: Vec<Entry>) -> Vec<Entry> {
+        entries
+            .into_iter()
+            .filter(|entry| self.keeps(entry))
+            .map(|mut entry| {
+                if let (Some(category), "Uncategorized") = (&self.category, entry.category.as_str())
+                {
+                    entry.category = category.clone();
+                }
+                entry
+            })
+            .collect()
+    }
+
+    /// An entry mentions a word when its name, category or one of its tags
+    /// contains it, ignoring case.
+    fn keeps(&self, entry: &Entry) -> bool {
+        let words: Vec<String> = [&entry.name, &entry.category]
+            .into_iter()
+            .chain(&entry.tags)
+            .map(|word| word.to_lowercase())
+            .collect();
+        let mentions = |pattern: &String| {
+            words
+                .iter()
+                .any(|word| word.contains(&pattern.to_lowercase()))
+        };
+        self.since.is_none_or(|since| entry.start >= since)
+            && self.until.is_none_or(|until| entry.start < until)
+            && (self.include.is_empty() || self.include.iter().any(mentions))
+            && !self.exclude.iter().any(mentions)
+    }
+}
+
+/// Harvest only records hours per day, so its entries are laid end to end
+/// from this time of day, in the order the file lists them.
+const HARVEST_DAY_START: (u32, u32) = (9, 0);
+
+/// Reads what `source` left at `path`.
+pub fn read(source: ImportSource, path: &Path) -> Result<Vec<Entry>, Box<dyn Error>> {
+    match source {
+        ImportSource::Timewarrior => read_timewarrior(path),
+        ImportSource::Watson => read_watson(File::open(path)?),
+        ImportSource::Ics => read_ics(&fs::read_to_string(path)?, Utc::now()),
+        ImportSource::Timeclock => read_timeclock(&fs::read_to_string(path)?),
+        ImportSource::Toggl | ImportSource::Clockify | ImportSource::Harvest => {
+            read_csv(source, File::open(path)?)
+        }
+    }
+}
+
+/// Reads a CSV export. Toggl and Clockify rows carry their own start and end;
+/// times without an offset are taken as local time.
+fn read_csv(source: ImportSource, input: impl Read) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(input);
+    let headers: HashMap<String, usize> = reader
+        .headers()?
+        .iter()
+        .enumerate()
+        .map(|(index, header)| (header.trim().to_lowercase(), index))
+        .collect();
+
+    let mut entries = Vec::new();
+    let mut harvest_days: HashMap<NaiveDate, DateTime<Utc>> = HashMap::new();
+    for (number, record) in reader.records().enumerate() {
+        let record = record?;
+        let field = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| headers.get(*name).and_then(|&index| record.get(index)))
+                .map(str::trim)
+                .unwrap_or("")
+        };
+        let row = |e: String| format!("Row {}: {}", number + 2, e);
+
+        let mut tags: BTreeSet<String> = field(&["tags"])
+            .split(',')
+            .map(slug)
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        if is_yes(field(&["billable", "billable?"])) {
+            tags.insert(String::from("billable"));
+        }
+        let (description, task) = match source {
+            ImportSource::Harvest => (field(&["notes"]), field(&["task"])),
+            _ => (field(&["description"]), field(&["task"])),
+        };
+        // The tracker's task becomes the name when there is no description,
+        // and a tag otherwise.
+        let mut lines = description.lines();
+        let name = match lines.next().map(str::trim).filter(|line| !line.is_empty()) {
+            Some(first) => {
+                if !task.is_empty() {
+                    tags.insert(slug(task));
+                }
+                clean_name(first)
+            }
+            None if !task.is_empty() => clean_name(task),
+            None => match field(&["project"]) {
+                "" => String::from("(no description)"),
+                project => clean_name(project),
+            },
+        };
+        let notes = if lines.next().is_some() {
+            vec![description.to_string()]
+        } else {
+            Vec::new()
+        };
+
+        let (start, end) = match source {
+            ImportSource::Toggl | ImportSource::Clockify => (
+                point(field(&["start date"]), field(&["start time"])).map_err(row)?,
+                point(field(&["end date"]), field(&["end time"])).map_err(row)?,
+            ),
+            _ => {
+                let date = date(field(&["date", "spent date"])).map_err(row)?;
+                let hours = hours(field(&["hours"])).map_err(row)?;
+                let start = match harvest_days.get(&date) {
+                    Some(&next) => next,
+                    None => {
+                        let (hour, minute) = HARVEST_DAY_START;
+                        let time = NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+                        utils::local_to_utc(date.and_time(time)).map_err(row)?
+                    }
+                };
+                harvest_days.insert(date, start + hours);
+                (start, start + hours)
+            }
+        };
+        if end <= start {
+            // Zero-length rows (e.g. a timer started and discarded) carry no time.
+            continue;
+        }
+
+        entries.push(Entry {
+            category: category(field(&["client"]), field(&["project"])),
+            name,
+            tags,
+            start,
+            end,
+            notes,
+        });
+    }
+    Ok(entries)
+}
+
+/// `#client/project`, `#project`, or Uncategorized without either.
+fn category(client: &str, project: &str) -> String {
+    let levels: Vec<String> = [client, project]
+        .iter()
+        .map(|level| slug(level))
+        .filter(|level| !level.is_empty())
+        .collect();
+    if levels.is_empty() {
+        String::from("Uncategorized")
+    } else {
+        format!("#{}", levels.join("/"))
+    }
+}
+
+/// Turns a project, client or tag name into one `#word`-safe word.
+fn slug(name: &str) -> String {
+    name.trim()
+        .trim_start_matches('#')
+        .split(|c: char| c.is_whitespace() || c == '/' || c == '#')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Task names cannot hold `#words`, which would read as categories or tags.
+fn clean_name(name: &str) -> String {
+    name.split_whitespace()
+        .map(|word| word.trim_start_matches('#'))
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_yes(value: &str) -> bool {
+    matches!(value.to_lowercase().as_str(), "yes" | "true" | "1")
+}
+
+fn date(input: &str) -> Result<NaiveDate, String> {
+    ["%Y-%m-%d", "%m/%d/%Y", "%d.%m.%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(input, format).ok())
+        .ok_or_else(|| format!("Could not understand the date {:?}", input))
+}
+
+fn point(date_input: &str, time_input: &str) -> Result<DateTime<Utc>, String> {
+    let time = ["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(time_input, format).ok())
+        .ok_or_else(|| format!("Could not understand the time {:?}", time_input))?;
+    utils::local_to_utc(date(date_input)?.and_time(time))
+}
+
+/// Decimal hours such as `1.5`, or anything `utils::parse_duration` reads.
+fn hours(input: &str) -> Result<Duration, String> {
+    match input.parse::<f64>() {
+        Ok(hours) if hours >= 0.0 => Ok(Duration::seconds((hours * 3600.0).round() as i64)),
+        Ok(_) => Err(format!("Negative hours {:?}", input)),
+        Err(_) => utils::parse_duration(input),
+    }
+}
+
+/// Timewarrior and Watson only know tags: the first plain tag names the task
+/// and the others stay tags, while a `#tag` is taken as the category.
+fn from_tags(
+    tags: &[String],
+    category: Option<String>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    notes: Vec<String>,
+) -> Entry {
+    let mut category = category;
+    let mut name = None;
+    let mut rest = BTreeSet::new();
+    for tag in tags {
+        match tag.strip_prefix('#') {
+            Some(path) if category.is_none() && !category_levels(path).is_empty() => {
+                category = Some(format!("#{}", category_levels(path)))
+            }
+            _ if name.is_none() => name = Some(clean_name(tag)).filter(|name| !name.is_empty()),
+            _ => {
+                rest.insert(slug(tag));
+            }
+        }
+    }
+    let category = category.unwrap_or_else(|| String::from("Uncategorized"));
+    let name = name.unwrap_or_else(|| match category.strip_prefix('#') {
+        Some(path) => path.rsplit('/').next().unwrap_or(path).to_string(),
+        None => String::from("(no description)"),
+    });
+    Entry {
+        category,
+        name,
+        tags: rest.into_iter().filter(|tag| !tag.is_empty()).collect(),
+        start,
+        end,
+        notes,
+    }
+}
+
+/// A nested project path such as `acme/web`, each level made `#word`-safe.
+fn category_levels(path: &str) -> String {
+    path.split('/')
+        .map(slug)
+        .filter(|level| !level.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Reads Timewarrior's interval lines from a `.data` file, a directory of
+/// them, or the Timewarrior directory holding `data`. Open intervals are
+/// still being tracked there and are left out.
+fn read_timewarrior(path: &Path) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let dir = if path.join("data").is_dir() {
+        path.join("data")
+    } else {
+        path.to_path_buf()
+    };
+    let files = if dir.is_dir() {
+        let mut files: Vec<PathBuf> = fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| {
+                file.extension()
+                    .is_some_and(|extension| extension == "data")
+            })
+            .collect();
+        files.sort();
+        files
+    } else {
+        vec![dir]
+    };
+
+    let mut entries = Vec::new();
+    for file in files {
+        for (number, line) in fs::read_to_string(&file)?.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry =
+                interval(line).map_err(|e| format!("{:?} line {}: {}", file, number + 1, e))?;
+            entries.extend(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Parses `inc <start> [- <end>] [# <tags> [# <annotation>]]`.
+fn interval(line: &str) -> Result<Option<Entry>, String> {
+    let tokens = timewarrior_tokens(line)?;
+    let word = |index: usize| {
+        tokens
+            .get(index)
+            .filter(|(_, quoted)| !quoted)
+            .map(|(token, _)| token.as_str())
+    };
+    if word(0) != Some("inc") {
+        return Err(String::from("Expected an interval starting with \"inc\""));
+    }
+    let timestamp = |token: Option<&str>| {
+        token
+            .and_then(|token| NaiveDateTime::parse_from_str(token, TIMEWARRIOR_TIME).ok())
+            .map(|time| Utc.from_utc_datetime(&time))
+            .ok_or_else(|| format!("Could not understand the time in {:?}", line))
+    };
+    let start = timestamp(word(1))?;
+    let (end, mut rest) = match word(2) {
+        Some("-") => (Some(timestamp(word(3))?), 4),
+        _ => (None, 2),
+    };
+    let mut tags = Vec::new();
+    let mut annotation = Vec::new();
+    if word(rest) == Some("#") {
+        rest += 1;
+        while rest < tokens.len() && word(rest) != Some("#") {
+            tags.push(tokens[rest].0.clone());
+            rest += 1;
+        }
+        annotation.extend(tokens.iter().skip(rest + 1).map(|(token, _)| token.clone()));
+    }
+    let notes = match annotation.join(" ") {
+        text if text.is_empty() => Vec::new(),
+        text => vec![text],
+    };
+    Ok(end
+        .filter(|end| *end > start)
+        .map(|end| from_tags(&tags, None, start, end, notes)))
+}
+
+/// Timewarrior's timestamp, always in UTC.
+pub const TIMEWARRIOR_TIME: &str = "%Y%m%dT%H%M%SZ";
+
+/// Splits a Timewarrior line into words, keeping `"quoted words"` (with `\"`
+/// escapes) together and flagging them so a quoted `#` is not a separator.
+fn timewarrior_tokens(line: &str) -> Result<Vec<(String, bool)>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut token = String::new();
+            loop {
+                match chars.next() {
+                    Some('\\') => token.extend(chars.next()),
+                    Some('"') => break,
+                    Some(c) => token.push(c),
+                    None => return Err(format!("Unterminated quote in {:?}", line)),
+                }
+            }
+            tokens.push((token, true));
+        } else {
+            let mut token = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+            tokens.push((token, false));
+        }
+    }
+    Ok(tokens)
+}
+
+/// A Watson frame: `[start, stop, project, id, tags, updated_at]`, with
+/// times as Unix timestamps.
+#[derive(Deserialize)]
+struct Frame {
+    start: i64,
+    stop: i64,
+    project: String,
+    _id: IgnoredAny,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    _updated_at: Option<IgnoredAny>,
+}
+
+/// Reads Watson's `frames` file. The project is the category, so a task name
+/// has to come from the frame's first tag, or else from the project.
+fn read_watson(input: impl Read) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let frames: Vec<Frame> = serde_json::from_reader(input)?;
+    let mut entries = Vec::new();
+    for Frame {
+        start,
+        stop,
+        project,
+        tags,
+        ..
+    } in frames
+    {
+        let time = |seconds: i64| {
+            Utc.timestamp_opt(seconds, 0)
+                .single()
+                .ok_or_else(|| format!("Timestamp {} is out of range", seconds))
+        };
+        let (start, end) = (time(start)?, time(stop)?);
+        if end <= start {
+            continue;
+        }
+        let category = match category_levels(&project) {
+            levels if levels.is_empty() || levels == "Uncategorized" => {
+                String::from("Uncategorized")
+            }
+            levels => format!("#{}", levels),
+        };
+        entries.push(from_tags(&tags, Some(category), start, end, Vec::new()));
+    }
+    Ok(entries)
+}
+
+/// A property's parameters and its raw value.
+type Property = (HashMap<String, String>, String);
+
+/// The properties of one VEVENT, by name.
+#[derive(Default)]
+struct CalendarEvent {
+    properties: HashMap<String, Vec<Property>>,
+}
+
+impl CalendarEvent {
+    fn first(&self, name: &str) -> Option<&Property> {
+        self.properties.get(name)?.first()
+    }
+
+    fn all(&self, name: &str) -> impl Iterator<Item = &Property> {
+        self.properties.get(name).into_iter().flatten()
+    }
+
+    fn text(&self, name: &str) -> Option<String> {
+        self.first(name).map(|(_, value)| calendar_text(value))
+    }
+
+    fn is(&self, name: &str, value: &str) -> bool {
+        self.first(name)
+            .is_some_and(|(_, actual)| actual.trim().eq_ignore_ascii_case(value))
+    }
+}
+
+/// Where a calendar time is read: UTC when it ends in `Z`, the zone its TZID
+/// names, or local time for floating times and zones chrono-tz doesn't know.
+#[derive(Clone, Copy)]
+enum Zone {
+    Utc,
+    Named(Tz),
+    Local,
+}
+
+impl Zone {
+    fn to_utc(self, time: NaiveDateTime) -> Result<DateTime<Utc>, String> {
+        match self {
+            Zone::Utc => Ok(Utc.from_utc_datetime(&time)),
+            Zone::Named(zone) => zone
+                .from_local_datetime(&time)
+                .earliest()
+                .map(|time| time.with_timezone(&Utc))
+                .ok_or_else(|| format!("{} does not exist in {}", time, zone)),
+            Zone::Local => utils::local_to_utc(time),
+        }
+    }
+}
+
+/// Reads the events of an iCalendar file as tracked time. Only time that was
+/// spent counts: cancelled events, events shown as free, all-day events and
+/// events that haven't ended by `now` are left out. The summary names the
+/// task, a `#category` among the categories files it, and the other
+/// categories become tags.
+fn read_ics(text: &str, now: DateTime<Utc>) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let events = calendar_events(text)?;
+    // An event with a RECURRENCE-ID replaces that occurrence of its series.
+    let mut moved: HashMap<String, Vec<DateTime<Utc>>> = HashMap::new();
+    for event in &events {
+        if let (Some(uid), Some((params, value))) =
+            (event.text("UID"), event.first("RECURRENCE-ID"))
+        {
+            if let Some((time, zone)) = calendar_time(params, value)? {
+                moved.entry(uid).or_default().push(zone.to_utc(time)?);
+            }
+        }
+    }
+
+    let mut entries = Vec::new();
+    for event in &events {
+        if event.is("STATUS", "CANCELLED") || event.is("TRANSP", "TRANSPARENT") {
+            continue;
+        }
+        let Some((start, zone)) = (match event.first("DTSTART") {
+            Some((params, value)) => calendar_time(params, value)?,
+            None => None,
+        }) else {
+            continue;
+        };
+        let first = zone.to_utc(start)?;
+        let length = match (event.first("DTEND"), event.first("DURATION")) {
+            (Some((params, value)), _) => match calendar_time(params, value)? {
+                Some((end, end_zone)) => end_zone.to_utc(end)? - first,
+                None => continue,
+            },
+            (None, Some((_, value))) => calendar_duration(value)?,
+            (None, None) => continue,
+        };
+        if length <= Duration::zero() {
+            continue;
+        }
+
+        let summary = clean_name(&event.text("SUMMARY").unwrap_or_default());
+        let mut skipped = Vec::new();
+        for (params, value) in event.all("EXDATE") {
+            for value in value.split(',') {
+                if let Some((time, zone)) = calendar_time(params, value)? {
+                    skipped.push(zone.to_utc(time)?);
+                }
+            }
+        }
+        let starts = match event.first("RRULE") {
+            Some((_, rule)) if event.first("RECURRENCE-ID").is_none() => {
+                if let Some(moved) = event.text("UID").and_then(|uid| moved.get(&uid)) {
+                    skipped.extend(moved);
+                }
+                occurrences(start, zone, rule, now).unwrap_or_else(|e| {
+                    eprintln!("Importing only the first {:?}: {}", summary, e);
+                    vec![first]
+                })
+            }
+            _ => vec![first],
+        };
+
+        let mut tags: Vec<String> = Some(summary)
+            .filter(|summary| !summary.is_empty())
+            .into_iter()
+            .collect();
+        tags.extend(
+            event
+                .all("CATEGORIES")
+                .flat_map(|(_, value)| calendar_texts(value))
+                .map(|category| category.trim().to_string())
+                .filter(|category| !category.is_empty()),
+        );
+        let notes: Vec<String> = event
+            .text("DESCRIPTION")
+            .filter(|description| !description.trim().is_empty())
+            .into_iter()
+            .collect();
+        for start in starts {
+            if !skipped.contains(&start) && start + length <= now {
+                entries.push(from_tags(&tags, None, start, start + length, notes.clone()));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Unfolds the calendar's lines and collects the properties of each VEVENT.
+/// Components nested in an event, such as alarms, are left out.
+fn calendar_events(text: &str) -> Result<Vec<CalendarEvent>, String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut events = Vec::new();
+    let mut current: Option<CalendarEvent> = None;
+    let mut nested = 0;
+    for line in lines.iter().filter(|line| !line.trim().is_empty()) {
+        let (name, params, value) = content_line(line)
+            .ok_or_else(|| format!("Could not read the calendar line {:?}", line))?;
+        match (name.as_str(), value.trim().to_uppercase().as_str()) {
+            ("BEGIN", "VEVENT") if current.is_none() => current = Some(CalendarEvent::default()),
+            ("BEGIN", _) if current.is_some() => nested += 1,
+            ("END", "VEVENT") if nested == 0 => events.extend(current.take()),
+            ("END", _) if nested > 0 => nested -= 1,
+            _ if nested == 0 => {
+                if let Some(event) = current.as_mut() {
+                    event
+                        .properties
+                        .entry(name)
+                        .or_default()
+                        .push((params, value));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(events)
+}
+
+/// Splits `NAME;PARAM=value;...:value`. Quoted parameter values may hold `:`
+/// and `;`.
+fn content_line(line: &str) -> Option<(String, HashMap<String, String>, String)> {
+    let mut parts = Vec::new();
+    let (mut quoted, mut from) = (false, 0);
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                parts.push(&line[from..index]);
+                from = index + 1;
+            }
+            ':' if !quoted => {
+                parts.push(&line[from..index]);
+                let name = parts[0].trim().to_uppercase();
+                let params = parts[1..]
+                    .iter()
+                    .filter_map(|param| param.split_once('='))
+                    .map(|(key, value)| {
+                        (
+                            key.trim().to_uppercase(),
+                            value.trim_matches('"').to_string(),
+                        )
+                    })
+                    .collect();
+                return Some((name, params, line[index + 1..].to_string()))
+                    .filter(|(name, ..)| !name.is_empty());
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Splits a list of TEXT values on unescaped commas and undoes the escapes.
+fn calendar_texts(value: &str) -> Vec<String> {
+    let mut texts = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        let text = texts.last_mut().unwrap();
+        match c {
+            '\\' => match chars.next() {
+                Some('n') | Some('N') => text.push('\n'),
+                Some(escaped) => text.push(escaped),
+                None => {}
+            },
+            ',' => texts.push(String::new()),
+            c => text.push(c),
+        }
+    }
+    texts
+}
+
+fn calendar_text(value: &str) -> String {
+    calendar_texts(value).join(",")
+}
+
+/// A DATE-TIME value and where to read it, or `None` for a bare DATE.
+fn calendar_time(
+    params: &HashMap<String, String>,
+    value: &str,
+) -> Result<Option<(NaiveDateTime, Zone)>, String> {
+    let value = value.trim();
+    if params
+        .get("VALUE")
+        .is_some_and(|kind| kind.eq_ignore_ascii_case("DATE"))
+        || value.len() == 8
+    {
+        return Ok(None);
+    }
+    let (value, zone) = match value.strip_suffix('Z') {
+        Some(value) => (value, Zone::Utc),
+        None => (
+            value,
+            params
+                .get("TZID")
+                .and_then(|id| id.parse::<Tz>().ok())
+                .map_or(Zone::Local, Zone::Named),
+        ),
+    };
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .map(|time| Some((time, zone)))
+        .map_err(|_| format!("Could not understand the time {:?}", value))
+}
+
+/// An iCalendar DURATION such as `PT1H30M` or `P1D`.
+fn calendar_duration(input: &str) -> Result<Duration, String> {
+    let invalid = || format!("Could not understand the duration {:?}", input);
+    let input = input.trim();
+    let (negative, rest) = match input.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, input.trim_start_matches('+')),
+    };
+    let rest = rest.strip_prefix('P').ok_or_else(invalid)?;
+    let (mut total, mut number, mut in_time) = (Duration::zero(), String::new(), false);
+    for c in rest.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        if c == 'T' && number.is_empty() {
+            in_time = true;
+            continue;
+        }
+        let amount: i64 = number.parse().map_err(|_| invalid())?;
+        number.clear();
+        total = total
+            + match (c, in_time) {
+                ('W', false) => Duration::weeks(amount),
+                ('D', false) => Duration::days(amount),
+                ('H', true) => Duration::hours(amount),
+                ('M', true) => Duration::minutes(amount),
+                ('S', true) => Duration::seconds(amount),
+                _ => return Err(invalid()),
+            };
+    }
+    if !number.is_empty() {
+        return Err(invalid());
+    }
+    Ok(if negative { -total } else { total })
+}
+
+/// The starts of a repeating event up to `now`, for the repeat rules
+/// calendars write for meetings: every n days, weeks (on given weekdays),
+/// months or years, a number of times or until a date.
+fn occurrences(
+    start: NaiveDateTime,
+    zone: Zone,
+    rule: &str,
+    now: DateTime<Utc>,
+) -> Result<Vec<DateTime<Utc>>, String> {
+    let unsupported = || format!("the repeat rule {:?} is not supported", rule);
+    let parts: HashMap<String, String> = rule
+        .split(';')
+        .filter_map(|part| part.split_once('='))
+        .map(|(key, value)| (key.trim().to_uppercase(), value.trim().to_uppercase()))
+        .collect();
+    let frequency = parts.get("FREQ").ok_or_else(unsupported)?.as_str();
+    let interval: u32 = match parts.get("INTERVAL") {
+        Some(interval) => interval
+            .parse()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(unsupported)?,
+        None => 1,
+    };
+    let count: Option<usize> = match parts.get("COUNT") {
+        Some(count) => Some(count.parse().map_err(|_| unsupported())?),
+        None => None,
+    };
+    let until = match parts.get("UNTIL") {
+        Some(until) if until.len() == 8 => {
+            let day = NaiveDate::parse_from_str(until, "%Y%m%d").map_err(|_| unsupported())?;
+            zone.to_utc(day.and_hms_opt(23, 59, 59).unwrap())?
+        }
+        Some(until) => match calendar_time(&HashMap::new(), until)? {
+            Some((time, until_zone)) => match until_zone {
+                Zone::Utc => Zone::Utc.to_utc(time)?,
+                _ => zone.to_utc(time)?,
+            },
+            None => return Err(unsupported()),
+        },
+        None => now,
+    }
+    .min(now);
+    let mut weekdays: Vec<Weekday> = match parts.get("BYDAY") {
+        Some(days) if frequency == "WEEKLY" => days
+            .split(',')
+            .map(|day| weekday(day).ok_or_else(unsupported))
+            .collect::<Result<_, _>>()?,
+        Some(_) => return Err(unsupported()),
+        None => vec![start.weekday()],
+    };
+    weekdays.sort_by_key(|day| day.num_days_from_monday());
+    if parts
+        .keys()
+        .any(|key| !["FREQ", "INTERVAL", "COUNT", "UNTIL", "BYDAY", "WKST"].contains(&key.as_str()))
+    {
+        return Err(unsupported());
+    }
+
+    let first = start.date();
+    let last = until.date_naive() + Days::new(1);
+    let mut starts = Vec::new();
+    let mut generated = 0;
+    for step in 0u32.. {
+        let step = step.checked_mul(interval).ok_or_else(unsupported)?;
+        let (window, dates): (NaiveDate, Vec<NaiveDate>) = match frequency {
+            "DAILY" => {
+                let day = first
+                    .checked_add_days(Days::new(step.into()))
+                    .ok_or_else(unsupported)?;
+                (day, vec![day])
+            }
+            "WEEKLY" => {
+                let monday = first
+                    .checked_sub_days(Days::new(first.weekday().num_days_from_monday().into()))
+                    .and_then(|monday| monday.checked_add_days(Days::new(7 * u64::from(step))))
+                    .ok_or_else(unsupported)?;
+                let days = weekdays
+                    .iter()
+                    .map(|day| monday + Days::new(day.num_days_from_monday().into()))
+                    .collect();
+                (monday, days)
+            }
+            "MONTHLY" => {
+                let month = first.month0().checked_add(step).ok_or_else(unsupported)?;
+                let year = first.year() + (month / 12) as i32;
+                let window =
+                    NaiveDate::from_ymd_opt(year, month % 12 + 1, 1).ok_or_else(unsupported)?;
+                (
+                    window,
+                    NaiveDate::from_ymd_opt(year, month % 12 + 1, first.day())
+                        .into_iter()
+                        .collect(),
+                )
+            }
+            "YEARLY" => {
+                let year = i32::try_from(step)
+                    .ok()
+                    .and_then(|step| first.year().checked_add(step))
+                    .ok_or_else(unsupported)?;
+                let window = NaiveDate::from_ymd_opt(year, 1, 1).ok_or_else(unsupported)?;
+                (
+                    window,
+                    NaiveDate::from_ymd_opt(year, first.month(), first.day())
+                        .into_iter()
+                        .collect(),
+                )
+            }
+            _ => return Err(unsupported()),
+        };
+        if window > last {
+            break;
+        }
+        for day in dates.into_iter().filter(|day| *day >= first) {
+            if count.is_some_and(|count| generated >= count) {
+                return Ok(starts);
+            }
+            generated += 1;
+            // A start that falls into a clock change doesn't happen.
+            let Ok(at) = zone.to_utc(day.and_time(start.time())) else {
+                continue;
+            };
+            if at > until {
+                return Ok(starts);
+            }
+            starts.push(at);
+        }
+    }
+    Ok(starts)
+}
+
+fn weekday(code: &str) -> Option<Weekday> {
+    Some(match code.trim() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+/// A timeclock `i` line still waiting for its `o`.
+struct ClockIn {
+    at: DateTime<Utc>,
+    account: String,
+    description: String,
+    /// The comment after it and those on indented lines right below it.
+    notes: Vec<String>,
+}
+
+/// Reads a timeclock file's `i`/`o` lines, in local time. The account is the
+/// category path and the description names the task, or else the account's
+/// last level does; comments on the `i` and `o` lines and on indented lines
+/// right below the `i` line become notes. An `o` naming an account clocks
+/// out of that account, otherwise out of the latest clock-in. Clock-ins still
+/// open at the end are running and are left out.
+fn read_timeclock(text: &str) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let mut open: Vec<ClockIn> = Vec::new();
+    let mut entries = Vec::new();
+    // Whether the last line clocked in, so indented comments belong to it.
+    let mut noting = false;
+    for (number, line) in text.lines().enumerate() {
+        let line_error = |e: String| format!("Line {}: {}", number + 1, e);
+        if let Some(comment) = line.trim_start().strip_prefix(';') {
+            let comment = comment.trim();
+            match open.last_mut() {
+                Some(clock_in) if noting && line.starts_with(char::is_whitespace) => {
+                    if !comment.is_empty() {
+                        clock_in.notes.push(comment.to_string());
+                    }
+                }
+                _ => noting = false,
+            }
+            continue;
+        }
+        noting = false;
+        if line.trim().is_empty() || line.starts_with(['#', '*']) {
+            continue;
+        }
+        let (code, rest) = first_word(line);
+        let (date_input, rest) = first_word(rest);
+        let (time_input, rest) = first_word(rest);
+        let day = ["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d"]
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(date_input, format).ok())
+            .ok_or_else(|| line_error(format!("could not understand the date {:?}", date_input)))?;
+        let time = ["%H:%M:%S", "%H:%M"]
+            .iter()
+            .find_map(|format| NaiveTime::parse_from_str(time_input, format).ok())
+            .ok_or_else(|| line_error(format!("could not understand the time {:?}", time_input)))?;
+        let at = utils::local_to_utc(day.and_time(time)).map_err(line_error)?;
+
+        // What follows the time: `<account>  <description>  ; <comment>`.
+        let rest = rest.trim();
+        let (rest, comment) = match rest.find(" ;").or_else(|| rest.find("\t;")) {
+            Some(index) => (&rest[..index], rest[index + 2..].trim()),
+            None => match rest.strip_prefix(';') {
+                Some(comment) => ("", comment.trim()),
+                None => (rest, ""),
+            },
+        };
+        let (account, description) = match rest.split_once("  ").or_else(|| rest.split_once('\t')) {
+            Some((account, description)) => (account.trim(), description.trim()),
+            None => (rest.trim(), ""),
+        };
+        match code {
+            "i" | "I" => {
+                open.push(ClockIn {
+                    at,
+                    account: account.to_string(),
+                    description: description.to_string(),
+                    notes: Some(comment)
+                        .filter(|comment| !comment.is_empty())
+                        .map(String::from)
+                        .into_iter()
+                        .collect(),
+                });
+                noting = true;
+            }
+            "o" | "O" => {
+                let index = open
+                    .iter()
+                    .rposition(|clock_in| !account.is_empty() && clock_in.account == account)
+                    .or_else(|| open.len().checked_sub(1))
+                    .ok_or_else(|| line_error(String::from("clocking out without clocking in")))?;
+                let clock_in = open.remove(index);
+                if at <= clock_in.at {
+                    continue;
+                }
+                let category = match category_levels(&clock_in.account.replace(':', "/")) {
+                    levels if levels.is_empty() || levels == "Uncategorized" => {
+                        String::from("Uncategorized")
+                    }
+                    levels => format!("#{}", levels),
+                };
+                let name: Vec<String> = Some(clean_name(&clock_in.description))
+                    .filter(|name| !name.is_empty())
+                    .into_iter()
+                    .collect();
+                let mut notes = clock_in.notes;
+                if !comment.is_empty() {
+                    notes.push(comment.to_string());
+                }
+                entries.push(from_tags(&name, Some(category), clock_in.at, at, notes));
+            }
+            other => return Err(line_error(format!("expected `i` or `o`, got {:?}", other)).into()),
+        }
+    }
+    Ok(entries)
+}
+
+/// Splits off the first whitespace-separated word.
+fn first_word(input: &str) -> (&str, &str) {
+    let input = input.trim_start();
+    match input.find(char::is_whitespace) {
+        Some(index) => (&input[..index], &input[index..]),
+        None => (input, ""),
+    }
+}
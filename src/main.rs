@@ -1,17 +1,33 @@
+mod backup;
 mod categorization;
 mod config;
 mod data;
+mod events;
+mod export;
+mod integration;
+mod status;
+mod storage;
 mod summary;
+mod tui;
+mod utils;
 mod visualization;
 
 use crate::categorization::Categorization;
 use crate::config::AppConfig;
-use crate::data::{load_data, save_data, Task, TaskStatus, TimeChunk, TimePeriod};
-use crate::summary::print_summary_with_duration;
+use crate::config::{BackupRetention, RunningPolicy, StorageBackend};
+use crate::data::{Note, Task, TaskStatus, TimeChunk, TimePeriod};
+use crate::events::{Action, Displaced, Effect, Journal, TaskChange};
+use crate::export::{ExportFilter, ExportFormat};
+use crate::integration::ImportSource;
+use crate::status::status_path;
+use crate::summary::{format_duration, print_summary_with_duration};
+use crate::utils::Picked;
 use crate::visualization::visualize_data;
-use chrono::{DateTime, Utc};
-use std::path::PathBuf;
-use std::time::Duration;
+use chrono::{DateTime, Local, Utc};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
@@ -24,205 +40,2129 @@ struct Opt {
 #[derive(StructOpt, Debug)]
 enum Command {
     Start {
-        #[structopt(help = "Task name or description")]
+        #[structopt(help = "Task name, task ID or description, e.g. \"Fix login #backend\"")]
         task: Vec<String>,
+        #[structopt(long, help = "Create a new task even if a similar one exists")]
+        new: bool,
     },
-    Stop,
-    Pause,
-    Resume,
-    Status,
-    List,
+    Add {
+        #[structopt(help = "Task name or description, e.g. \"Fix login bug #backend\"")]
+        task: Vec<String>,
+        #[structopt(long, help = "When the work started, e.g. 09:15")]
+        from: Option<String>,
+        #[structopt(long, help = "When the work ended, e.g. 10:40")]
+        to: Option<String>,
+        #[structopt(long, help = "How long the work took, e.g. 1h25m")]
+        duration: Option<String>,
+        #[structopt(long, help = "Day the times refer to, e.g. yesterday (default today)")]
+        date: Option<String>,
+        #[structopt(
+            long,
+            help = "Resolve overlaps without asking: trim (the new entry) or split (existing entries)"
+        )]
+        on_overlap: Option<OverlapPolicy>,
+    },
+    Edit {
+        #[structopt(
+            help = "Task name (with its #category if not unique), task ID or chunk ID; asks if left out"
+        )]
+        task: Option<String>,
+        #[structopt(
+            long,
+            help = "Chunk number as shown by `edit <task>` (default the last)"
+        )]
+        chunk: Option<usize>,
+        #[structopt(long, help = "New start time, e.g. 09:15")]
+        start: Option<String>,
+        #[structopt(long, help = "New end time, e.g. 10:40")]
+        end: Option<String>,
+        #[structopt(long, help = "Move the chunk to another task")]
+        move_to: Option<String>,
+        #[structopt(long, help = "Split the chunk in two at this time")]
+        split_at: Option<String>,
+        #[structopt(long, help = "Delete the chunk")]
+        delete: bool,
+    },
+    Delete {
+        #[structopt(
+            help = "Task name (with its #category if not unique), task ID or chunk ID; asks if left out"
+        )]
+        task: Option<String>,
+        #[structopt(long, help = "Delete only this chunk instead of the whole task")]
+        chunk: Option<usize>,
+    },
+    Rename {
+        #[structopt(help = "Task name or ID")]
+        task: String,
+        #[structopt(help = "New name")]
+        new_name: Vec<String>,
+    },
+    Move {
+        #[structopt(help = "Task name or ID")]
+        task: String,
+        #[structopt(help = "Category to file the task under, e.g. #backend")]
+        category: String,
+    },
+    Merge {
+        #[structopt(help = "Task whose chunks are moved; it is removed afterwards")]
+        task: String,
+        #[structopt(help = "Task that receives the chunks")]
+        into: String,
+    },
+    Category {
+        #[structopt(subcommand)]
+        command: CategoryCommand,
+    },
+    Tag {
+        #[structopt(help = "Task name or ID")]
+        task: String,
+        #[structopt(required = true, help = "Tags to add, e.g. #bugfix #meeting")]
+        tags: Vec<String>,
+        #[structopt(long, help = "Remove the tags instead of adding them")]
+        remove: bool,
+    },
+    Note {
+        #[structopt(
+            long,
+            help = "Task name, task ID or chunk ID to annotate (default: the running or paused task)"
+        )]
+        on: Option<String>,
+        #[structopt(required = true, help = "The note, e.g. \"blocked on review\"")]
+        text: Vec<String>,
+    },
+    Search {
+        #[structopt(required = true, help = "Text to look for in task names and notes")]
+        query: Vec<String>,
+    },
+    Stop {
+        #[structopt(help = "Task name or ID (default: the running or paused task)")]
+        task: Option<String>,
+    },
+    Pause {
+        #[structopt(help = "Task name or ID (default: the running task)")]
+        task: Option<String>,
+    },
+    Resume {
+        #[structopt(help = "Task name or ID (default: the most recently paused task)")]
+        task: Option<String>,
+    },
+    Status {
+        #[structopt(
+            long,
+            help = "One line from a template with {name}, {category}, {tags}, {status}, {elapsed}, {total} and {today}"
+        )]
+        format: Option<String>,
+        #[structopt(long, conflicts_with = "format", help = "Print the status as JSON")]
+        json: bool,
+    },
+    List {
+        #[structopt(long = "tag", help = "Only list tasks carrying this tag (repeatable)")]
+        tags: Vec<String>,
+    },
+    /// Writes one row per time chunk: task_id, name, category, tags, start,
+    /// end, duration (seconds) and notes, always in that order
     Export {
-        #[structopt(long, parse(from_os_str), help = "File path to export data")]
-        file_path: PathBuf,
+        #[structopt(
+            long,
+            default_value = "csv",
+            help = "csv, json, ndjson, timewarrior, watson, ics or timeclock"
+        )]
+        format: ExportFormat,
+        #[structopt(long, help = "Only chunks starting at or after this time")]
+        from: Option<String>,
+        #[structopt(long, help = "Only chunks starting before this time")]
+        to: Option<String>,
+        #[structopt(long, help = "Only this category and the categories nested in it")]
+        category: Option<String>,
+        #[structopt(
+            long,
+            short = "o",
+            parse(from_os_str),
+            help = "File to write to (default: standard output); a directory gets Timewarrior's monthly files"
+        )]
+        file_path: Option<PathBuf>,
+    },
+    /// Imports another tracker's data, showing what would change first, e.g.
+    /// `tt import --from toggl export.csv`, `tt import watson frames` or
+    /// `tt import ics calendar.ics --include standup`
+    Import {
+        #[structopt(
+            long = "from",
+            help = "toggl, clockify, harvest, timewarrior, watson, ics or timeclock"
+        )]
+        from: Option<ImportSource>,
+        #[structopt(
+            help = "The source unless given with --from, then the file or directory to read"
+        )]
+        args: Vec<String>,
+        #[structopt(
+            long,
+            help = "Only entries whose name, category or tags mention this (repeatable)"
+        )]
+        include: Vec<String>,
+        #[structopt(
+            long,
+            help = "Leave out entries whose name, category or tags mention this (repeatable)"
+        )]
+        exclude: Vec<String>,
+        #[structopt(long, help = "Only entries starting at or after this time")]
+        since: Option<String>,
+        #[structopt(long, help = "Only entries starting before this time")]
+        until: Option<String>,
+        #[structopt(long, help = "Category for entries that come without one")]
+        category: Option<String>,
+        #[structopt(long, help = "Only show what would be imported")]
+        dry_run: bool,
+        #[structopt(long, help = "Import without asking for confirmation")]
+        yes: bool,
     },
     Summary {
         #[structopt(help = "Time period for the summary: daily, weekly, or monthly")]
         period: String,
+        #[structopt(long = "tag", help = "Only count tasks carrying this tag (repeatable)")]
+        tags: Vec<String>,
+        #[structopt(long, help = "Only summarise this category and those nested in it")]
+        category: Option<String>,
+        #[structopt(long, help = "Number of nested levels to show in the category tree")]
+        depth: Option<usize>,
     },
     Configure {
         #[structopt(long, help = "Custom storage location for data file")]
-        storage_location: String,
+        storage_location: Option<String>,
+        #[structopt(long, help = "Storage backend: json or sqlite")]
+        backend: Option<StorageBackend>,
+        #[structopt(long, help = "Number of most recent backups to keep")]
+        keep_recent: Option<usize>,
+        #[structopt(long, help = "Number of hours to keep one backup for")]
+        keep_hourly: Option<usize>,
+        #[structopt(long, help = "Number of days to keep one backup for")]
+        keep_daily: Option<usize>,
+        #[structopt(long, help = "Minutes to wait after a backup before taking another")]
+        backup_interval: Option<u32>,
+        #[structopt(
+            long,
+            help = "What starting a task does to the running one: stop, pause or parallel"
+        )]
+        running_policy: Option<RunningPolicy>,
+    },
+    Visualize {
+        #[structopt(long, help = "Only chart this category and those nested in it")]
+        category: Option<String>,
+        #[structopt(long, help = "One row per category, rolled up to this many levels")]
+        depth: Option<usize>,
+        #[structopt(
+            long,
+            short = "o",
+            parse(from_os_str),
+            help = "PNG file to write (default: chart.png next to the data file)"
+        )]
+        output: Option<PathBuf>,
     },
-    Visualize,
     Clear,
+    /// Full-screen dashboard of the running task, today and every task
+    Tui,
+    Recover {
+        #[structopt(long, parse(from_os_str), help = "Repaired data file to restore")]
+        restore: Option<PathBuf>,
+        #[structopt(long, help = "Discard the corrupt data and start with an empty file")]
+        start_fresh: bool,
+    },
+    Backup {
+        #[structopt(subcommand)]
+        command: BackupCommand,
+    },
+    Log {
+        #[structopt(
+            long,
+            default_value = "20",
+            help = "Number of most recent events to show"
+        )]
+        limit: usize,
+    },
+    Undo {
+        #[structopt(default_value = "1", help = "Number of changes to undo")]
+        count: usize,
+    },
+    Redo {
+        #[structopt(default_value = "1", help = "Number of changes to redo")]
+        count: usize,
+    },
+    History {
+        #[structopt(
+            long,
+            default_value = "10",
+            help = "Number of recent operations to show"
+        )]
+        limit: usize,
+    },
+    At {
+        #[structopt(help = "Point in time, e.g. \"14:00 last tuesday\" or \"2024-03-05 09:30\"")]
+        when: Vec<String>,
+    },
+}
+
+impl Command {
+    /// Whether the command records changes, and so first repairs data that
+    /// breaks the running policy. Reading commands show the data as it is,
+    /// and undo and redo would otherwise step through the repair itself.
+    fn changes_data(&self) -> bool {
+        matches!(
+            self,
+            Command::Start { .. }
+                | Command::Add { .. }
+                | Command::Edit { .. }
+                | Command::Delete { .. }
+                | Command::Rename { .. }
+                | Command::Move { .. }
+                | Command::Merge { .. }
+                | Command::Category { .. }
+                | Command::Tag { .. }
+                | Command::Note { .. }
+                | Command::Stop { .. }
+                | Command::Pause { .. }
+                | Command::Resume { .. }
+                | Command::Import { .. }
+                | Command::Clear
+        )
+    }
+}
+
+#[derive(StructOpt, Debug)]
+enum BackupCommand {
+    List,
+    Restore {
+        #[structopt(help = "Backup id (or a unique prefix of it) from `backup list`")]
+        id: String,
+        #[structopt(long, help = "Restore without asking for confirmation")]
+        yes: bool,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+enum CategoryCommand {
+    Rename {
+        category: String,
+        new_name: String,
+    },
+    /// Deletes a category, moving its tasks to Uncategorized
+    Delete {
+        category: String,
+    },
+    /// Moves every task of one category into another, merging tasks that
+    /// share a name
+    Merge {
+        category: String,
+        into: String,
+    },
+}
+
+/// What `add` does when the new entry covers time that is already tracked.
+#[derive(Debug, Clone, Copy)]
+enum OverlapPolicy {
+    /// Shrink the new entry to the time nothing else covers.
+    Trim,
+    /// Cut the new entry's time out of the existing entries.
+    Split,
+}
+
+impl FromStr for OverlapPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "trim" => Ok(OverlapPolicy::Trim),
+            "split" => Ok(OverlapPolicy::Split),
+            other => Err(format!(
+                "Unknown overlap policy {:?}; use trim or split",
+                other
+            )),
+        }
+    }
 }
 
 fn main() {
     let opt = Opt::from_args();
 
     let app_config = AppConfig::load();
-    let storage_location = app_config.storage_location.unwrap_or_else(|| {
-        let mut default_path = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
-        default_path.push("time_tracker");
-        default_path.push("tasks.json");
-        default_path
-    });
+    // The dashboard takes the lock itself, only while it reads or writes.
+    if let Command::Tui = opt.command {
+        if let Err(e) = tui::run(&app_config) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // Status lines are polled every second, so they read the small status
+    // file instead of loading everything, and do not wait for the lock.
+    if let Command::Status { format, json } = &opt.command {
+        if format.is_some() || *json {
+            print_status_line(&app_config, format.as_deref());
+            return;
+        }
+    }
+    let _lock = exit_on_error(storage::lock(&app_config.storage_path()));
 
-    // Change the type of `time_period`
-    let mut time_period: TimePeriod = load_data(&storage_location);
+    if let Command::Recover {
+        restore,
+        start_fresh,
+    } = &opt.command
+    {
+        exit_on_error(recover(&app_config, restore.as_deref(), *start_fresh));
+        return;
+    }
+
+    let mut storage = exit_on_error(storage::open(&app_config));
+    let (mut journal, mut time_period) = exit_on_error(Journal::open(storage.as_mut()));
+    let journal = &mut journal;
+    if opt.command.changes_data() {
+        exit_on_error(repair_running_tasks(
+            &mut time_period,
+            journal,
+            app_config.running_policy,
+        ));
+    }
 
     // Update function calls accordingly
     match opt.command {
-        Command::Start { task } => {
+        Command::Start { task, new } => {
             // Join the task Vec<String> with spaces
-            println!("{:?}", task);
-            let task = task.join(" ");
-            start_task(&mut time_period, &task, Utc::now(), &storage_location)
+            let (task, new) = if task.is_empty() {
+                match pick_task_to_start(&time_period) {
+                    Some(picked) => picked,
+                    None => {
+                        println!("Nothing started. Give a task name, e.g. `tt start write docs`.");
+                        return;
+                    }
+                }
+            } else {
+                (task.join(" "), new)
+            };
+            exit_on_error(start_task(
+                &mut time_period,
+                &task,
+                Utc::now(),
+                journal,
+                app_config.running_policy,
+                new,
+            ))
+        }
+        Command::Add {
+            task,
+            from,
+            to,
+            duration,
+            date,
+            on_overlap,
+        } => {
+            let (start, end) = entry_range(from, to, duration, date).unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            });
+            exit_on_error(add_entry(
+                &mut time_period,
+                journal,
+                &task.join(" "),
+                start,
+                end,
+                on_overlap,
+            ))
+        }
+        Command::Edit {
+            task,
+            chunk,
+            start,
+            end,
+            move_to,
+            split_at,
+            delete,
+        } => {
+            let edit = match (start.is_some() || end.is_some(), move_to, split_at, delete) {
+                (false, None, None, false) => Ok(None),
+                (true, None, None, false) => Ok(Some(ChunkEdit::Times { start, end })),
+                (false, Some(target), None, false) => Ok(Some(ChunkEdit::MoveTo(target))),
+                (false, None, Some(at), false) => Ok(Some(ChunkEdit::SplitAt(at))),
+                (false, None, None, true) => Ok(Some(ChunkEdit::Delete)),
+                _ => Err(String::from(
+                    "Use one of --start/--end, --move-to, --split-at or --delete at a time",
+                )),
+            };
+            exit_on_error(edit.and_then(|edit| {
+                let task = task_or_ask(&time_period, task, "Which task do you want to edit?")?;
+                edit_chunk(&mut time_period, journal, &task, chunk, edit)
+            }))
+        }
+        Command::Delete { task, chunk } => exit_on_error(
+            task_or_ask(&time_period, task, "Which task do you want to delete?")
+                .and_then(|task| delete(&mut time_period, journal, &task, chunk)),
+        ),
+        Command::Rename { task, new_name } => exit_on_error(rename_task(
+            &mut time_period,
+            journal,
+            &task,
+            &new_name.join(" "),
+        )),
+        Command::Move { task, category } => {
+            exit_on_error(move_task(&mut time_period, journal, &task, &category))
+        }
+        Command::Merge { task, into } => {
+            exit_on_error(merge_tasks(&mut time_period, journal, &task, &into))
         }
-        Command::Stop => stop_task(&mut time_period, &storage_location),
-        Command::Pause => pause_task(&mut time_period),
-        Command::Resume => resume_task(&mut time_period),
-        Command::List => list_tasks(&mut time_period),
-        Command::Status => clock(&mut time_period),
-        Command::Export { file_path } => export_data(file_path),
-        Command::Summary { period } => generate_summary(&time_period, period, None),
-        Command::Configure { storage_location } => {
-            configure_app(Some(PathBuf::from(storage_location + "tasks.json")))
+        Command::Category { command } => exit_on_error(match command {
+            CategoryCommand::Rename { category, new_name } => {
+                rename_category(&mut time_period, journal, &category, &new_name)
+            }
+            CategoryCommand::Delete { category } => {
+                merge_categories(&mut time_period, journal, &category, UNCATEGORIZED)
+            }
+            CategoryCommand::Merge { category, into } => {
+                merge_categories(&mut time_period, journal, &category, &into)
+            }
+        }),
+        Command::Tag { task, tags, remove } => {
+            exit_on_error(tag_task(&mut time_period, journal, &task, &tags, remove))
+        }
+        Command::Note { on, text } => exit_on_error(add_note(
+            &mut time_period,
+            journal,
+            on.as_deref(),
+            &text.join(" "),
+        )),
+        Command::Search { query } => search(&time_period, &query.join(" ")),
+        Command::Stop { task } => exit_on_error(stop_task(&mut time_period, journal, task)),
+        Command::Pause { task } => exit_on_error(pause_task(&mut time_period, journal, task)),
+        Command::Resume { task } => exit_on_error(resume_task(
+            &mut time_period,
+            journal,
+            task,
+            app_config.running_policy,
+        )),
+        Command::List { tags } => list_tasks(&time_period, &normalize_tags(&tags)),
+        Command::Status { .. } => clock(&mut time_period),
+        Command::Export {
+            format,
+            from,
+            to,
+            category,
+            file_path,
+        } => exit_on_error(export_data(
+            &time_period,
+            format,
+            from,
+            to,
+            category,
+            file_path,
+        )),
+        Command::Import {
+            from,
+            args,
+            include,
+            exclude,
+            since,
+            until,
+            category,
+            dry_run,
+            yes,
+        } => exit_on_error(import_source(from, &args).and_then(|(source, path)| {
+            let now = Local::now();
+            let point = |input: Option<String>| {
+                input
+                    .map(|input| utils::parse_datetime(&input, now))
+                    .transpose()
+            };
+            let rules = integration::Rules {
+                include,
+                exclude,
+                since: point(since)?,
+                until: point(until)?,
+                category: category.as_deref().map(normalize_category),
+            };
+            let entries = integration::read(source, &path)
+                .map_err(|e| format!("Could not read {:?}: {}", path, e))?;
+            import_entries(
+                &mut time_period,
+                journal,
+                source,
+                rules.apply(entries),
+                dry_run,
+                yes,
+            )
+        })),
+        Command::Summary {
+            period,
+            tags,
+            category,
+            depth,
+        } => generate_summary(
+            &time_period,
+            period,
+            category.as_deref().map(normalize_category),
+            &normalize_tags(&tags),
+            depth,
+        ),
+        Command::Configure {
+            storage_location,
+            backend,
+            keep_recent,
+            keep_hourly,
+            keep_daily,
+            backup_interval,
+            running_policy,
+        } => {
+            let retention = BackupRetention {
+                recent: keep_recent.unwrap_or(app_config.backup_retention.recent),
+                hourly: keep_hourly.unwrap_or(app_config.backup_retention.hourly),
+                daily: keep_daily.unwrap_or(app_config.backup_retention.daily),
+                interval_minutes: backup_interval
+                    .unwrap_or(app_config.backup_retention.interval_minutes),
+            };
+            exit_on_error(configure_app(
+                storage_location.map(PathBuf::from),
+                backend,
+                retention,
+                running_policy,
+            ))
+        }
+        Command::Visualize {
+            category,
+            depth,
+            output,
+        } => exit_on_error(visualize(
+            &time_period,
+            &output.unwrap_or_else(|| app_config.storage_path().with_file_name("chart.png")),
+            category.as_deref().map(normalize_category).as_deref(),
+            depth,
+        )),
+        Command::Clear => exit_on_error(clear(&mut time_period, journal)),
+        Command::Recover { .. } | Command::Tui => unreachable!("handled before loading data"),
+        Command::Log { limit } => show_log(journal, limit),
+        Command::Undo { count } => exit_on_error(undo(&mut time_period, journal, count)),
+        Command::Redo { count } => exit_on_error(redo(&mut time_period, journal, count)),
+        Command::History { limit } => show_history(journal, limit),
+        Command::At { when } => exit_on_error(show_at(&time_period, &when.join(" "))),
+        Command::Backup { command } => {
+            let backup_dir = backup::backup_dir(&app_config.storage_path());
+            match command {
+                BackupCommand::List => exit_on_error(list_backups(&backup_dir)),
+                BackupCommand::Restore { id, yes } => exit_on_error(restore_backup(
+                    &mut time_period,
+                    journal,
+                    &backup_dir,
+                    &id,
+                    yes,
+                )),
+            }
         }
-        Command::Visualize => visualize(&time_period),
-        Command::Clear => clear(&mut time_period, &storage_location),
     }
 }
 
+/// Starts the task `description` refers to, reopening an existing task in
+/// place or creating a new one. A bare name may refer to a task in any
+/// category, or be a task ID; unless `new` is set, a name close to an
+/// existing one asks whether that was meant.
 fn start_task(
     time_period: &mut TimePeriod,
-    name: &str,
+    description: &str,
     start_time: DateTime<Utc>,
-    storage_location: &PathBuf,
-) {
-    let category = categorization::Categorization::extract_category_from_description(name);
-    //println!("Category: {:?}", category);
-    if time_period
+    journal: &mut Journal,
+    policy: RunningPolicy,
+    new: bool,
+) -> Result<(), String> {
+    let (category, name, tags) = Categorization::parse_description(description);
+    let mut target =
+        find_task(time_period, &category, &name).map(|_| (category.clone(), name.clone()));
+    if target.is_none() && !new && !description.contains('#') {
+        target = match resolve(time_period, description.trim()) {
+            Ok((category, name, _)) => Some((category, name)),
+            Err(e) if !task_categories(time_period, description.trim()).is_empty() => {
+                return Err(e)
+            }
+            Err(_) => None,
+        };
+    }
+    if target.is_none() && !new {
+        let similar = similar_tasks(time_period, &name);
+        if !similar.is_empty() {
+            let mut choices: Vec<String> = similar
+                .iter()
+                .map(|(category, name)| format!("{} ({})", name, category))
+                .collect();
+            choices.push(format!("Create a new task {:?}", name));
+            let choices: Vec<&str> = choices.iter().map(String::as_str).collect();
+            match utils::choose("Did you mean...", &choices) {
+                Some(index) if index < similar.len() => target = Some(similar[index].clone()),
+                Some(_) => {}
+                None => {
+                    println!(
+                        "Nothing started. Did you mean {}? Start it by name or ID, or pass --new to create {:?}.",
+                        similar
+                            .iter()
+                            .map(|(_, name)| format!("{:?}", name))
+                            .collect::<Vec<_>>()
+                            .join(" or "),
+                        name
+                    );
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    let description = match &target {
+        Some((category, name)) if category == UNCATEGORIZED => name.clone(),
+        Some((category, name)) => format!("{} {}", name, category),
+        None => description.to_string(),
+    };
+    let message = match target
+        .as_ref()
+        .and_then(|(category, name)| find_task(time_period, category, name))
+    {
+        Some(task) if task.status == TaskStatus::Running => {
+            println!("{:?} is already running, no changes made.", task.name);
+            return Ok(());
+        }
+        Some(task) if task.status == TaskStatus::Paused => format!("Resuming {:?}", task.name),
+        Some(task) => format!("Reopening {:?}", task.name),
+        None => String::from("Creating new task"),
+    };
+    let displaced = displaced_tasks(time_period, policy, None);
+    record(
+        time_period,
+        journal,
+        Action::Start {
+            description,
+            at: start_time,
+            task_id: data::new_id(),
+            chunk_id: data::new_id(),
+            tags: Some(tags),
+            displaced: displaced.clone(),
+        },
+    )?;
+    println!("{}", message);
+    report_displaced(&displaced);
+    Ok(())
+}
+
+/// Tasks that `wanted` accepts, most used first: each chunk counts for more
+/// the more recently it started.
+fn recent_tasks(time_period: &TimePeriod, wanted: impl Fn(&Task) -> bool) -> Vec<(&String, &Task)> {
+    let now = Utc::now();
+    let score = |task: &Task| {
+        task.time_chunks
+            .iter()
+            .map(|chunk| {
+                let days = (now - chunk.start_time).num_seconds().max(0) as f64 / 86_400.0;
+                1.0 / (1.0 + days)
+            })
+            .sum::<f64>()
+    };
+    let mut tasks: Vec<(f64, &String, &Task)> = time_period
         .categorization
         .categories
-        .contains_key(&category.0.clone())
-    {
-    } else {
-        time_period.categorization.add_category(category.0.clone());
+        .iter()
+        .flat_map(|(category, tasks)| tasks.iter().map(move |task| (category, task)))
+        .filter(|(_, task)| wanted(task))
+        .map(|(category, task)| (score(task), category, task))
+        .collect();
+    tasks.sort_by(|a, b| {
+        b.0.total_cmp(&a.0)
+            .then_with(|| (a.1, &a.2.name).cmp(&(b.1, &b.2.name)))
+    });
+    tasks
+        .into_iter()
+        .map(|(_, category, task)| (category, task))
+        .collect()
+}
+
+fn task_label(category: &str, task: &Task) -> String {
+    format!("{} ({})", task.name, category)
+}
+
+/// Asks for one of the tasks `wanted` accepts and returns its ID, or `None`
+/// if there is no terminal to ask on or nothing was picked.
+fn ask_for_task(
+    time_period: &TimePeriod,
+    message: &str,
+    wanted: impl Fn(&Task) -> bool,
+) -> Option<String> {
+    let tasks = recent_tasks(time_period, wanted);
+    let labels: Vec<String> = tasks
+        .iter()
+        .map(|(category, task)| task_label(category, task))
+        .collect();
+    match utils::pick(message, &labels, false)? {
+        Picked::Item(index) => Some(tasks[index].1.id.clone()),
+        Picked::New(_) => None,
     }
+}
+
+/// `task` if given, or else whichever task the user picks.
+fn task_or_ask(
+    time_period: &TimePeriod,
+    task: Option<String>,
+    message: &str,
+) -> Result<String, String> {
+    match task {
+        Some(task) => Ok(task),
+        None => ask_for_task(time_period, message, |_| true)
+            .ok_or_else(|| String::from("No task given")),
+    }
+}
+
+/// Asks which task `tt start` without arguments should start: an existing
+/// one by ID, or a new one by name (the `bool` is then set).
+fn pick_task_to_start(time_period: &TimePeriod) -> Option<(String, bool)> {
+    let tasks = recent_tasks(time_period, |task| task.status != TaskStatus::Running);
+    let labels: Vec<String> = tasks
+        .iter()
+        .map(|(category, task)| task_label(category, task))
+        .collect();
+    match utils::pick("Which task do you want to start?", &labels, true)? {
+        Picked::Item(index) => Some((tasks[index].1.id.clone(), false)),
+        Picked::New(name) => Some((name, true)),
+    }
+}
 
-    let existing_task = time_period
+/// Existing tasks whose names look like `name`, best match first.
+fn similar_tasks(time_period: &TimePeriod, name: &str) -> Vec<(String, String)> {
+    let name = name.to_lowercase();
+    let mut similar: Vec<(f64, String, String)> = time_period
         .categorization
         .categories
-        .get_key_value(&category.0)
-        .unwrap()
-        .1
         .iter()
-        .find(|task| task.name == name);
+        .flat_map(|(category, tasks)| tasks.iter().map(move |task| (category, task)))
+        .map(|(category, task)| {
+            let score = strsim::normalized_damerau_levenshtein(&name, &task.name.to_lowercase());
+            (score, category.clone(), task.name.clone())
+        })
+        .filter(|(score, _, _)| *score >= 0.7)
+        .collect();
+    similar.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    similar
+        .into_iter()
+        .take(3)
+        .map(|(_, category, name)| (category, name))
+        .collect()
+}
 
+/// Works out when a retroactive entry started and ended from whichever of
+/// `--from`, `--to` and `--duration` were given. Times of day refer to
+/// `--date`, or today.
+fn entry_range(
+    from: Option<String>,
+    to: Option<String>,
+    duration: Option<String>,
+    date: Option<String>,
+) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    let now = Local::now();
+    let day = utils::parse_date(date.as_deref().unwrap_or(""), now.date_naive())?;
+    let point = |input: &str| match utils::parse_time_of_day(input) {
+        Some(time) => utils::local_to_utc(day.and_time(time)),
+        None => utils::parse_datetime(input, now),
+    };
+    let duration = duration.as_deref().map(utils::parse_duration).transpose()?;
+
+    let (start, end) = match (from.as_deref(), to.as_deref(), duration) {
+        (Some(from), Some(to), None) => (point(from)?, point(to)?),
+        (Some(from), None, Some(duration)) => {
+            let start = point(from)?;
+            (start, start + duration)
+        }
+        (None, Some(to), Some(duration)) => {
+            let end = point(to)?;
+            (end - duration, end)
+        }
+        (None, None, Some(duration)) if date.is_none() => {
+            let end = now.with_timezone(&Utc);
+            (end - duration, end)
+        }
+        _ => {
+            return Err(String::from(
+                "Give two of --from, --to and --duration (or just --duration to end now)",
+            ))
+        }
+    };
+    if end <= start {
+        return Err(String::from("The entry must end after it starts"));
+    }
+    if end > Utc::now() {
+        return Err(String::from("The entry must not end in the future"));
+    }
+    Ok((start, end))
+}
+
+/// Adds already finished work from `start` to `end`, resolving overlaps
+/// with existing chunks according to `on_overlap` or by asking.
+fn add_entry(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    description: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    on_overlap: Option<OverlapPolicy>,
+) -> Result<(), String> {
+    let now = Utc::now();
+    let (category, name, tags) = Categorization::parse_description(description);
+
+    let overlapping: Vec<(&String, &Task, &TimeChunk)> = time_period
+        .categorization
+        .categories
+        .iter()
+        .flat_map(|(category, tasks)| tasks.iter().map(move |task| (category, task)))
+        .flat_map(|(category, task)| {
+            task.time_chunks
+                .iter()
+                .map(move |chunk| (category, task, chunk))
+        })
+        .filter(|(_, _, chunk)| chunk.overlaps(start, end, now))
+        .collect();
+
+    let policy = if overlapping.is_empty() {
+        None
+    } else {
+        println!(
+            "The new entry overlaps {} existing entries:",
+            overlapping.len()
+        );
+        for (category, task, chunk) in &overlapping {
+            println!(
+                "  {} ({}) {} - {}",
+                task.name,
+                category,
+                chunk
+                    .start_time
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M"),
+                chunk
+                    .end_time
+                    .map(|t| t.with_timezone(&Local).format("%H:%M").to_string())
+                    .unwrap_or_else(|| String::from("now"))
+            );
+        }
+        let policy = on_overlap.or_else(|| {
+            match utils::choose(
+                "How should the overlap be resolved?",
+                &[
+                    "Trim the new entry to the free time",
+                    "Split the existing entries around it",
+                    "Cancel",
+                ],
+            ) {
+                Some(0) => Some(OverlapPolicy::Trim),
+                Some(1) => Some(OverlapPolicy::Split),
+                _ => None,
+            }
+        });
+        if policy.is_none() {
+            println!("Nothing added. Use --on-overlap trim or --on-overlap split to resolve overlaps without asking.");
+            return Ok(());
+        }
+        policy
+    };
+
+    let mut edited = time_period.clone();
+    let mut new_chunks = vec![TimeChunk::new(start, Some(end))];
+    match policy {
+        Some(OverlapPolicy::Trim) => {
+            for (_, _, existing) in &overlapping {
+                let existing_end = existing.end_time.unwrap_or(now);
+                new_chunks = new_chunks
+                    .iter()
+                    .flat_map(|chunk| chunk.without(existing.start_time, existing_end))
+                    .collect();
+            }
+        }
+        Some(OverlapPolicy::Split) => {
+            for task in edited.categorization.categories.values_mut().flatten() {
+                task.time_chunks = task
+                    .time_chunks
+                    .iter()
+                    .flat_map(|chunk| chunk.without(start, end))
+                    .collect();
+            }
+        }
+        None => {}
+    }
+    if new_chunks.is_empty() {
+        println!("Nothing added: that time is already tracked.");
+        return Ok(());
+    }
+    let added = new_chunks
+        .iter()
+        .fold(chrono::Duration::zero(), |acc, chunk| {
+            acc + chunk.duration(now)
+        });
+
+    let existing_task = edited
+        .categorization
+        .categories
+        .get_mut(&category)
+        .and_then(|tasks| tasks.iter_mut().find(|task| task.name == name));
     match existing_task {
         Some(task) => {
-            println!("Found existing task");
-            let mut task = task.clone();
-            match task.status {
-                TaskStatus::Running => {
-                    println!("Task already running, no changes made.");
-                }
-                TaskStatus::Paused => {
-                    println!("Task already exists, resuming...");
+            task.time_chunks.extend(new_chunks);
+            task.time_chunks.sort_by_key(|chunk| chunk.start_time);
+            task.tags.extend(tags);
+        }
+        None => edited
+            .categorization
+            .add_task_to_category(Task::from_chunks(description, new_chunks)),
+    }
+    record_changes(time_period, journal, &edited)?;
+    println!(
+        "Added {} to {:?} ({})",
+        format_duration(added),
+        name,
+        category
+    );
+    Ok(())
+}
 
-                    task.status = TaskStatus::Running
+/// Records every task that differs between `time_period` and `edited` as a
+/// single undoable edit. Tasks are matched by ID, so renames and moves
+/// between categories are kept apart from deletions.
+fn record_changes(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    edited: &TimePeriod,
+) -> Result<(), String> {
+    let by_id = |time_period: &TimePeriod, category: &str, id: &str| {
+        time_period
+            .categorization
+            .categories
+            .get(category)
+            .and_then(|tasks| tasks.iter().find(|task| task.id == id))
+            .cloned()
+    };
+    let mut changes = Vec::new();
+    for (category, tasks) in sorted_categories(time_period) {
+        for task in tasks {
+            if by_id(edited, category, &task.id).is_none() {
+                changes.push(TaskChange {
+                    category: category.clone(),
+                    before: Some(task.clone()),
+                    after: None,
+                });
+            }
+        }
+    }
+    for (category, tasks) in sorted_categories(edited) {
+        for task in tasks {
+            let before = by_id(time_period, category, &task.id);
+            if before.as_ref() != Some(task) {
+                changes.push(TaskChange {
+                    category: category.clone(),
+                    before,
+                    after: Some(task.clone()),
+                });
+            }
+        }
+    }
+    if changes.is_empty() {
+        return Ok(());
+    }
+    record(time_period, journal, Action::Edit { changes })
+}
+
+/// Records `action`, turning a failure to save it into a message for the
+/// user.
+fn record(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    action: Action,
+) -> Result<(), String> {
+    journal
+        .record(time_period, action)
+        .map_err(|e| e.to_string())
+}
+
+fn sorted_categories(time_period: &TimePeriod) -> Vec<(&String, &Vec<Task>)> {
+    let mut categories: Vec<(&String, &Vec<Task>)> =
+        time_period.categorization.categories.iter().collect();
+    categories.sort_by_key(|(category, _)| *category);
+    categories
+}
+
+fn find_task<'a>(time_period: &'a TimePeriod, category: &str, name: &str) -> Option<&'a Task> {
+    time_period
+        .categorization
+        .categories
+        .get(category)
+        .and_then(|tasks| tasks.iter().find(|task| task.name == name))
+}
+
+fn remove_task(time_period: &mut TimePeriod, category: &str, name: &str) {
+    let categories = &mut time_period.categorization.categories;
+    if let Some(tasks) = categories.get_mut(category) {
+        tasks.retain(|task| task.name != name);
+        if tasks.is_empty() {
+            categories.remove(category);
+        }
+    }
+}
+
+/// The categories holding a task called `name`, sorted.
+fn task_categories<'a>(time_period: &'a TimePeriod, name: &str) -> Vec<&'a String> {
+    let mut categories: Vec<&String> = time_period
+        .categorization
+        .categories
+        .iter()
+        .filter(|(_, tasks)| tasks.iter().any(|task| task.name == name))
+        .map(|(category, _)| category)
+        .collect();
+    categories.sort();
+    categories
+}
+
+/// Finds a task by name, optionally qualified with its `#category`, or a task
+/// or chunk by a prefix of its ID. Returns the task's category and name and,
+/// for a chunk ID, the chunk's position.
+fn resolve(
+    time_period: &TimePeriod,
+    reference: &str,
+) -> Result<(String, String, Option<usize>), String> {
+    match resolve_name(time_period, reference) {
+        Ok((category, name)) => Ok((category, name, None)),
+        Err(e) => match time_period.find_id(reference)? {
+            Some((category, index, chunk)) => {
+                let name = time_period.categorization.categories[&category][index]
+                    .name
+                    .clone();
+                Ok((category, name, chunk))
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// Like `resolve`, for places that need a whole task.
+fn resolve_task(time_period: &TimePeriod, reference: &str) -> Result<(String, String), String> {
+    match resolve(time_period, reference)? {
+        (category, name, None) => Ok((category, name)),
+        (_, _, Some(_)) => Err(format!(
+            "{:?} is a chunk ID; give a task name or task ID",
+            reference
+        )),
+    }
+}
+
+/// A bare name must be unique across categories.
+fn resolve_name(time_period: &TimePeriod, reference: &str) -> Result<(String, String), String> {
+    if reference.contains('#') {
+        let (category, name, _) = Categorization::parse_description(reference);
+        return match find_task(time_period, &category, &name) {
+            Some(_) => Ok((category, name)),
+            None => Err(format!("No task {:?} in {}", name, category)),
+        };
+    }
+    let categories = task_categories(time_period, reference);
+    match categories.as_slice() {
+        [] => Err(format!("No task named {:?}", reference)),
+        [category] => Ok(((*category).clone(), reference.to_string())),
+        several => Err(format!(
+            "{:?} exists in several categories ({}); add the #category to pick one",
+            reference,
+            several
+                .iter()
+                .map(|category| category.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// A change to one chunk of a task.
+enum ChunkEdit {
+    Times {
+        start: Option<String>,
+        end: Option<String>,
+    },
+    MoveTo(String),
+    SplitAt(String),
+    Delete,
+}
+
+fn show_chunks(task: &Task, short_ids: &HashMap<String, String>) {
+    let now = Utc::now();
+    println!("{} {}", short_ids[&task.id], task.name);
+    for (number, chunk) in task.time_chunks.iter().enumerate() {
+        println!(
+            "  {}. {} {} - {} ({})",
+            number + 1,
+            short_ids[&chunk.id],
+            chunk
+                .start_time
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S"),
+            chunk
+                .end_time
+                .map(|t| t
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string())
+                .unwrap_or_else(|| String::from("running")),
+            format_duration(chunk.duration(now))
+        );
+        for note in &chunk.notes {
+            println!("     {}", note.text);
+        }
+    }
+}
+
+/// Applies `edit` to chunk `chunk` (1-based, default the last) of `task`.
+/// Times of day refer to the day the chunk started.
+fn edit_chunk(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    task: &str,
+    chunk: Option<usize>,
+    edit: Option<ChunkEdit>,
+) -> Result<(), String> {
+    let (category, name, chunk_by_id) = resolve(time_period, task)?;
+    let task = find_task(time_period, &category, &name).unwrap();
+    let edit = match edit {
+        Some(edit) => edit,
+        None => {
+            show_chunks(task, &time_period.short_ids());
+            return Ok(());
+        }
+    };
+    let index = match (chunk_by_id, chunk) {
+        (Some(_), Some(_)) => {
+            return Err(String::from("Give either a chunk ID or --chunk, not both"))
+        }
+        (Some(index), None) => index,
+        (None, chunk) => match chunk {
+            None => task
+                .time_chunks
+                .len()
+                .checked_sub(1)
+                .ok_or_else(|| format!("{:?} has no chunks", name))?,
+            Some(number) if (1..=task.time_chunks.len()).contains(&number) => number - 1,
+            Some(number) => {
+                return Err(format!(
+                    "{:?} has {} chunks; there is no chunk {}",
+                    name,
+                    task.time_chunks.len(),
+                    number
+                ))
+            }
+        },
+    };
+    let (edited, message) = chunk_edited(time_period, &category, &name, index, edit)?;
+    record_changes(time_period, journal, &edited)?;
+    println!("{}", message);
+    Ok(())
+}
+
+/// The data with `edit` applied to chunk `index` (0-based) of the task
+/// `name` in `category`, and what to tell the user about it.
+fn chunk_edited(
+    time_period: &TimePeriod,
+    category: &str,
+    name: &str,
+    index: usize,
+    edit: ChunkEdit,
+) -> Result<(TimePeriod, String), String> {
+    let now = Utc::now();
+    let (category, name) = (category.to_string(), name.to_string());
+    let task = find_task(time_period, &category, &name).unwrap();
+    let original = task.time_chunks[index].clone();
+    let day = original.start_time.with_timezone(&Local).date_naive();
+    let point = |input: &str| match utils::parse_time_of_day(input) {
+        Some(time) => utils::local_to_utc(day.and_time(time)),
+        None => utils::parse_datetime(input, now.with_timezone(&Local)),
+    };
+
+    let mut edited = time_period.clone();
+    let mut touched = vec![(category.clone(), name.clone())];
+    let task = edited
+        .categorization
+        .categories
+        .get_mut(&category)
+        .and_then(|tasks| tasks.iter_mut().find(|task| task.name == name))
+        .unwrap();
+    let message = match edit {
+        ChunkEdit::Times { start, end } => {
+            let chunk = &mut task.time_chunks[index];
+            if let Some(start) = start {
+                chunk.start_time = point(&start)?;
+            }
+            if let Some(end) = end {
+                if chunk.end_time.is_none() {
+                    return Err(String::from(
+                        "That chunk is still running; use `tt stop` to end it",
+                    ));
                 }
-                TaskStatus::Stopped => {
-                    task.status = TaskStatus::Running;
-                    task.time_chunks.push(TimeChunk {
-                        start_time,
-                        end_time: None,
-                    });
+                chunk.end_time = Some(point(&end)?);
+            }
+            if chunk.end_time.unwrap_or(now) < chunk.start_time {
+                return Err(String::from("A chunk must not end before it starts"));
+            }
+            if chunk.start_time > now || chunk.end_time.is_some_and(|end| end > now) {
+                return Err(String::from("A chunk must not reach into the future"));
+            }
+            task.time_chunks.sort_by_key(|chunk| chunk.start_time);
+            format!("Updated chunk {} of {:?}", index + 1, name)
+        }
+        ChunkEdit::SplitAt(at) => {
+            let at = point(&at)?;
+            if at <= original.start_time || at >= original.end_time.unwrap_or(now) {
+                return Err(format!(
+                    "{} is not inside chunk {} of {:?}",
+                    at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+                    index + 1,
+                    name
+                ));
+            }
+            task.time_chunks.splice(
+                index..=index,
+                [
+                    TimeChunk {
+                        end_time: Some(at),
+                        ..original.clone()
+                    },
+                    TimeChunk {
+                        notes: original.notes.clone(),
+                        ..TimeChunk::new(at, original.end_time)
+                    },
+                ],
+            );
+            format!("Split chunk {} of {:?} in two", index + 1, name)
+        }
+        ChunkEdit::Delete => {
+            let was_last = index + 1 == task.time_chunks.len();
+            task.time_chunks.remove(index);
+            if original.end_time.is_none() {
+                task.status = TaskStatus::Stopped;
+            }
+            // A pause follows the last chunk; without that chunk there is
+            // nothing left to resume.
+            if was_last && task.status == TaskStatus::Paused {
+                if task
+                    .pauses
+                    .last()
+                    .is_some_and(|pause| pause.end_time.is_none())
+                {
+                    task.pauses.pop();
                 }
+                task.status = TaskStatus::Stopped;
+            }
+            if task.time_chunks.is_empty() {
+                remove_task(&mut edited, &category, &name);
+                format!("Deleted {:?}, its only chunk", name)
+            } else {
+                format!("Deleted chunk {} of {:?}", index + 1, name)
             }
         }
-        None => {
-            println!("Creating new task");
-            let new_task = Task {
-                name: name.to_string(),
-                time_chunks: vec![TimeChunk {
-                    start_time,
-                    end_time: None,
-                }],
-                paused_duration: Duration::from_secs(0),
-                status: TaskStatus::Running,
+        ChunkEdit::MoveTo(target) => {
+            if original.end_time.is_none() {
+                return Err(String::from(
+                    "That chunk is still running; stop the task before moving it",
+                ));
+            }
+            task.time_chunks.remove(index);
+            if task.time_chunks.is_empty() {
+                remove_task(&mut edited, &category, &name);
+            }
+            let (target_category, target_name) = match resolve_task(&edited, &target) {
+                Ok(found) => found,
+                Err(_) if target.contains('#') || task_categories(&edited, &target).is_empty() => {
+                    let (category, name, _) = Categorization::parse_description(&target);
+                    (category, name)
+                }
+                Err(e) => return Err(e),
             };
-            time_period.categorization.add_task_to_category(new_task)
+            if (target_category.as_str(), target_name.as_str())
+                == (category.as_str(), name.as_str())
+            {
+                return Err(String::from("The chunk already belongs to that task"));
+            }
+            match edited
+                .categorization
+                .categories
+                .get_mut(&target_category)
+                .and_then(|tasks| tasks.iter_mut().find(|task| task.name == target_name))
+            {
+                Some(target) => {
+                    target.time_chunks.push(original);
+                    target.time_chunks.sort_by_key(|chunk| chunk.start_time);
+                }
+                None => edited
+                    .categorization
+                    .add_task_to_category(Task::from_chunks(&target, vec![original])),
+            }
+            let message = format!(
+                "Moved chunk {} of {:?} to {:?} ({})",
+                index + 1,
+                name,
+                target_name,
+                target_category
+            );
+            touched.push((target_category, target_name));
+            message
         }
-    }
-    save_data(storage_location, &time_period).unwrap();
+    };
+
+    check_running_overlap(&edited, &touched, now)?;
+    Ok((edited, message))
 }
 
-fn stop_task(time_period: &mut TimePeriod, storage_location: &PathBuf) {
-    let current_task = time_period
+/// Edits must not put closed chunks on top of the chunk that is running now.
+fn check_running_overlap(
+    time_period: &TimePeriod,
+    touched: &[(String, String)],
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    let running = time_period
         .categorization
         .categories
-        .values_mut()
-        .flat_map(|tasks| tasks.iter_mut())
-        .find(|task| task.status == TaskStatus::Running);
-
-    if let Some(task) = current_task {
-        task.status = TaskStatus::Stopped;
-        task.time_chunks.last_mut().unwrap().end_time = Some(Utc::now());
-        println!("Stopped current task: {:?}", task.name);
+        .values()
+        .flatten()
+        .filter(|task| task.status == TaskStatus::Running)
+        .filter_map(|task| {
+            let chunk = task.time_chunks.last()?;
+            chunk.end_time.is_none().then_some((task, chunk.start_time))
+        });
+    for (running_task, running_since) in running {
+        for (category, name) in touched {
+            let Some(task) = find_task(time_period, category, name) else {
+                continue;
+            };
+            if task
+                .time_chunks
+                .iter()
+                .filter(|chunk| chunk.end_time.is_some())
+                .any(|chunk| chunk.overlaps(running_since, now, now))
+            {
+                return Err(format!(
+                    "The change would overlap {:?}, which has been running since {}",
+                    running_task.name,
+                    running_since
+                        .with_timezone(&Local)
+                        .format("%Y-%m-%d %H:%M:%S")
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Deletes a whole task, or one chunk if `reference` is a chunk ID or
+/// `chunk` is given.
+fn delete(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    reference: &str,
+    chunk: Option<usize>,
+) -> Result<(), String> {
+    let (category, name, chunk_by_id) = resolve(time_period, reference)?;
+    if chunk.is_some() || chunk_by_id.is_some() {
+        return edit_chunk(
+            time_period,
+            journal,
+            reference,
+            chunk,
+            Some(ChunkEdit::Delete),
+        );
+    }
+    let mut edited = time_period.clone();
+    remove_task(&mut edited, &category, &name);
+    record_changes(time_period, journal, &edited)?;
+    println!(
+        "Deleted {:?} ({}); `tt undo` brings it back",
+        name, category
+    );
+    Ok(())
+}
+
+fn exit_on_error<T, E: fmt::Display>(result: Result<T, E>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    })
+}
+
+const UNCATEGORIZED: &str = "Uncategorized";
+
+/// Accepts a category with or without its leading `#`.
+fn normalize_category(category: &str) -> String {
+    let category = category.trim();
+    if category == UNCATEGORIZED || category.starts_with('#') {
+        category.to_string()
     } else {
-        println!("No task is currently running.");
+        format!("#{}", category)
+    }
+}
+
+/// Tags as given on the command line, with or without their `#`.
+fn normalize_tags(tags: &[String]) -> BTreeSet<String> {
+    tags.iter()
+        .map(|tag| tag.trim_start_matches('#').to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+fn format_tags(tags: &BTreeSet<String>) -> String {
+    tags.iter()
+        .map(|tag| format!("#{}", tag))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Attaches a note to a task, or to a single chunk when `on` is a chunk ID.
+fn add_note(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    on: Option<&str>,
+    text: &str,
+) -> Result<(), String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(String::from("The note must not be empty"));
+    }
+    let (category, name, chunk) = match on {
+        Some(reference) => resolve(time_period, reference)?,
+        None => {
+            let (category, name) = find_task_with_status(time_period, TaskStatus::Running)
+                .or_else(|| find_task_with_status(time_period, TaskStatus::Paused))
+                .ok_or("No running or paused task; use --on to pick one")?;
+            (category, name, None)
+        }
+    };
+
+    let mut edited = time_period.clone();
+    let tasks = edited.categorization.categories.get_mut(&category).unwrap();
+    let task = tasks.iter_mut().find(|task| task.name == name).unwrap();
+    let note = Note {
+        at: Utc::now(),
+        text: text.to_string(),
+    };
+    let message = match chunk {
+        Some(index) => {
+            task.time_chunks[index].notes.push(note);
+            format!("Added a note to chunk {} of {:?}", index + 1, name)
+        }
+        None => {
+            task.notes.push(note);
+            format!("Added a note to {:?} ({})", name, category)
+        }
+    };
+    record_changes(time_period, journal, &edited)?;
+    println!("{}", message);
+    Ok(())
+}
+
+/// Prints a task's notes and those of its chunks, oldest first.
+fn print_notes(task: &Task) {
+    let mut notes: Vec<(&Note, Option<usize>)> =
+        task.notes.iter().map(|note| (note, None)).collect();
+    for (index, chunk) in task.time_chunks.iter().enumerate() {
+        notes.extend(chunk.notes.iter().map(|note| (note, Some(index))));
+    }
+    notes.sort_by_key(|(note, _)| note.at);
+    for (note, chunk) in notes {
+        let at = note.at.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+        match chunk {
+            Some(index) => println!("Note {} (chunk {}): {}", at, index + 1, note.text),
+            None => println!("Note {}: {}", at, note.text),
+        }
+    }
+}
+
+/// Lists tasks whose name or notes contain `query`, ignoring case.
+fn search(time_period: &TimePeriod, query: &str) {
+    let query = query.to_lowercase();
+    let matches = |text: &str| text.to_lowercase().contains(&query);
+    let short_ids = time_period.short_ids();
+    let mut found = false;
+    for (category, tasks) in sorted_categories(time_period) {
+        for task in tasks {
+            let chunk_notes = task
+                .time_chunks
+                .iter()
+                .enumerate()
+                .flat_map(|(index, chunk)| chunk.notes.iter().map(move |note| (index, note)));
+            let hits: Vec<String> = task
+                .notes
+                .iter()
+                .filter(|note| matches(&note.text))
+                .map(|note| format!("  {}", note.text))
+                .chain(
+                    chunk_notes
+                        .filter(|(_, note)| matches(&note.text))
+                        .map(|(index, note)| format!("  (chunk {}) {}", index + 1, note.text)),
+                )
+                .collect();
+            if hits.is_empty() && !matches(&task.name) {
+                continue;
+            }
+            found = true;
+            println!("{} {} ({})", short_ids[&task.id], task.name, category);
+            for hit in hits {
+                println!("{}", hit);
+            }
+        }
+    }
+    if !found {
+        println!("Nothing matches {:?}", query);
+    }
+}
+
+/// Adds or removes tags other than the primary category, which `tt move`
+/// changes instead.
+fn tag_task(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    reference: &str,
+    tags: &[String],
+    remove: bool,
+) -> Result<(), String> {
+    let (category, name) = resolve_task(time_period, reference)?;
+    let tags = normalize_tags(tags);
+    if tags.is_empty() {
+        return Err(String::from("No tags given"));
+    }
+    if category
+        .strip_prefix('#')
+        .is_some_and(|primary| tags.contains(primary))
+    {
+        return Err(format!(
+            "{} is the task's category; use `tt move` to change it",
+            category
+        ));
+    }
+
+    let mut edited = time_period.clone();
+    let tasks = edited.categorization.categories.get_mut(&category).unwrap();
+    let task = tasks.iter_mut().find(|task| task.name == name).unwrap();
+    let before = task.tags.clone();
+    if remove {
+        task.tags.retain(|tag| !tags.contains(tag));
+    } else {
+        task.tags.extend(tags);
+    }
+    let now_tagged = format_tags(&task.tags);
+    if task.tags == before {
+        println!("No changes made.");
+        return Ok(());
+    }
+    record_changes(time_period, journal, &edited)?;
+    if now_tagged.is_empty() {
+        println!("{:?} ({}) has no other tags", name, category);
+    } else {
+        println!("{:?} ({}) is tagged {}", name, category, now_tagged);
     }
-    save_data(storage_location, &time_period).unwrap();
+    Ok(())
 }
 
-fn pause_task(time_period: &mut TimePeriod) {
-    let current_task = time_period
+fn rename_task(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    reference: &str,
+    new_name: &str,
+) -> Result<(), String> {
+    let (category, name) = resolve_task(time_period, reference)?;
+    let new_name = new_name.trim();
+    let edited = renamed(time_period, &category, &name, new_name)?;
+    record_changes(time_period, journal, &edited)?;
+    println!("Renamed {:?} to {:?} ({})", name, new_name, category);
+    Ok(())
+}
+
+/// A copy of `time_period` with the task `name` in `category` renamed.
+fn renamed(
+    time_period: &TimePeriod,
+    category: &str,
+    name: &str,
+    new_name: &str,
+) -> Result<TimePeriod, String> {
+    if new_name.is_empty() {
+        return Err(String::from("The new name must not be empty"));
+    }
+    if new_name.contains('#') {
+        return Err(String::from(
+            "Task names cannot contain #; use `tt move` to change the category",
+        ));
+    }
+    if find_task(time_period, category, new_name).is_some() {
+        return Err(format!(
+            "{} already has a task named {:?}; use `tt merge` to combine them",
+            category, new_name
+        ));
+    }
+
+    let mut edited = time_period.clone();
+    let tasks = edited.categorization.categories.get_mut(category).unwrap();
+    let task = tasks.iter_mut().find(|task| task.name == name).unwrap();
+    task.name = new_name.to_string();
+    Ok(edited)
+}
+
+fn move_task(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    reference: &str,
+    category: &str,
+) -> Result<(), String> {
+    let (from, name) = resolve_task(time_period, reference)?;
+    let to = normalize_category(category);
+    if from == to {
+        return Err(format!("{:?} is already in {}", name, to));
+    }
+    if find_task(time_period, &to, &name).is_some() {
+        return Err(format!(
+            "{} already has a task named {:?}; use `tt merge` to combine them",
+            to, name
+        ));
+    }
+
+    let mut edited = time_period.clone();
+    let task = find_task(&edited, &from, &name).unwrap().clone();
+    remove_task(&mut edited, &from, &name);
+    edited
         .categorization
         .categories
-        .values_mut()
-        .flat_map(|tasks| tasks.iter_mut())
-        .find(|task| task.status == TaskStatus::Running);
-
-    if let Some(task) = current_task {
-        task.status = TaskStatus::Paused;
-        task.time_chunks.last_mut().unwrap().end_time = Some(Utc::now());
-        println!("Paused current task: {:?}", task.name);
-    } else {
-        println!("No task is currently running.");
+        .entry(to.clone())
+        .or_default()
+        .push(task);
+    record_changes(time_period, journal, &edited)?;
+    println!("Moved {:?} from {} to {}", name, from, to);
+    Ok(())
+}
+
+fn merge_tasks(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    reference: &str,
+    into_reference: &str,
+) -> Result<(), String> {
+    let (from_category, from_name) = resolve_task(time_period, reference)?;
+    let (into_category, into_name) = resolve_task(time_period, into_reference)?;
+    if (&from_category, &from_name) == (&into_category, &into_name) {
+        return Err(String::from("Cannot merge a task into itself"));
     }
+
+    let mut edited = time_period.clone();
+    let from = find_task(&edited, &from_category, &from_name)
+        .unwrap()
+        .clone();
+    remove_task(&mut edited, &from_category, &from_name);
+    let chunks = from.time_chunks.len();
+    let into = edited
+        .categorization
+        .categories
+        .get_mut(&into_category)
+        .and_then(|tasks| tasks.iter_mut().find(|task| task.name == into_name))
+        .unwrap();
+    absorb(into, from)?;
+    record_changes(time_period, journal, &edited)?;
+    println!(
+        "Merged {:?} ({}) into {:?} ({}), adding {} chunks",
+        from_name, from_category, into_name, into_category, chunks
+    );
+    Ok(())
+}
+
+/// `Task::absorb`, refusing to end up with two open chunks.
+fn absorb(into: &mut Task, from: Task) -> Result<(), String> {
+    if into.status != TaskStatus::Stopped && from.status != TaskStatus::Stopped {
+        return Err(format!(
+            "Both {:?} and {:?} are active; stop one of them first",
+            from.name, into.name
+        ));
+    }
+    into.absorb(from);
+    Ok(())
 }
 
-fn resume_task(time_period: &mut TimePeriod) {
-    let paused_task = time_period
+fn rename_category(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    category: &str,
+    new_name: &str,
+) -> Result<(), String> {
+    let from = normalize_category(category);
+    let to = normalize_category(new_name);
+    // Categories nested below `from` move along with it.
+    let renames: Vec<(String, String)> = time_period
         .categorization
         .categories
-        .values_mut()
-        .flat_map(|tasks| tasks.iter_mut())
-        .find(|task| task.status == TaskStatus::Paused);
-
-    if let Some(task) = paused_task {
-        task.status = TaskStatus::Running;
-        task.time_chunks.push(TimeChunk {
-            start_time: Utc::now(),
-            end_time: None,
-        });
-        println!("Resumed task: {:?}", task.name);
+        .keys()
+        .filter(|name| Categorization::is_within(name, &from))
+        .map(|name| (name.clone(), format!("{}{}", to, &name[from.len()..])))
+        .collect();
+    if renames.is_empty() {
+        return Err(format!("No category {}", from));
+    }
+    if from != UNCATEGORIZED && Categorization::is_within(&to, &from) {
+        return Err(format!("Cannot move {} inside itself", from));
+    }
+    if let Some((_, taken)) = renames
+        .iter()
+        .find(|(_, to)| time_period.categorization.categories.contains_key(to))
+    {
+        return Err(format!(
+            "{} already exists; use `tt category merge` to combine categories",
+            taken
+        ));
+    }
+
+    let mut edited = time_period.clone();
+    for (old, new) in &renames {
+        let tasks = edited.categorization.categories.remove(old).unwrap();
+        edited.categorization.categories.insert(new.clone(), tasks);
+    }
+    record_changes(time_period, journal, &edited)?;
+    println!("Renamed {} to {}", from, to);
+    if renames.len() > 1 {
+        println!("Also moved {} nested categories", renames.len() - 1);
+    }
+    Ok(())
+}
+
+/// Moves every task in `category` into `into`, merging tasks of the same
+/// name, and removes `category`. Deleting a category merges it into
+/// Uncategorized.
+fn merge_categories(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    category: &str,
+    into: &str,
+) -> Result<(), String> {
+    let from = normalize_category(category);
+    let into = normalize_category(into);
+    if from == into {
+        return Err(format!("Cannot merge {} into itself", from));
+    }
+
+    let mut edited = time_period.clone();
+    let tasks = edited
+        .categorization
+        .categories
+        .remove(&from)
+        .ok_or_else(|| format!("No category {}", from))?;
+    let count = tasks.len();
+    let target = edited
+        .categorization
+        .categories
+        .entry(into.clone())
+        .or_default();
+    for task in tasks {
+        match target
+            .iter_mut()
+            .find(|existing| existing.name == task.name)
+        {
+            Some(existing) => absorb(existing, task)?,
+            None => target.push(task),
+        }
+    }
+    record_changes(time_period, journal, &edited)?;
+    println!("Moved {} tasks from {} into {}", count, from, into);
+    Ok(())
+}
+
+/// Tasks with `status`, most recently started (or, for paused tasks,
+/// paused) first.
+fn tasks_with_status(time_period: &TimePeriod, status: TaskStatus) -> Vec<(String, String)> {
+    let mut found: Vec<(DateTime<Utc>, String, String)> = time_period
+        .categorization
+        .categories
+        .iter()
+        .flat_map(|(category, tasks)| tasks.iter().map(move |task| (category, task)))
+        .filter(|(_, task)| task.status == status)
+        .map(|(category, task)| {
+            let since = match status {
+                TaskStatus::Paused => task.pauses.last(),
+                _ => task.time_chunks.last(),
+            };
+            (
+                since.map_or(DateTime::<Utc>::MIN_UTC, |chunk| chunk.start_time),
+                category.clone(),
+                task.name.clone(),
+            )
+        })
+        .collect();
+    found.sort_by(|a, b| b.cmp(a));
+    found
+        .into_iter()
+        .map(|(_, category, name)| (category, name))
+        .collect()
+}
+
+/// The first task in `status`, with the category it is filed under.
+fn find_task_with_status(time_period: &TimePeriod, status: TaskStatus) -> Option<(String, String)> {
+    tasks_with_status(time_period, status).into_iter().next()
+}
+
+/// The one place that decides what happens to running tasks when another
+/// starts or resumes: every running task except `keep` is stopped or paused
+/// according to `policy`.
+fn displaced_tasks(
+    time_period: &TimePeriod,
+    policy: RunningPolicy,
+    keep: Option<&(String, String)>,
+) -> Vec<Displaced> {
+    if policy == RunningPolicy::Parallel {
+        return Vec::new();
+    }
+    tasks_with_status(time_period, TaskStatus::Running)
+        .into_iter()
+        .filter(|running| Some(running) != keep)
+        .map(|(category, task)| Displaced {
+            category,
+            task,
+            paused: policy == RunningPolicy::AutoPause,
+        })
+        .collect()
+}
+
+fn report_displaced(displaced: &[Displaced]) {
+    for other in displaced {
+        let verb = if other.paused { "Paused" } else { "Stopped" };
+        println!("{} {:?} ({})", verb, other.task, other.category);
+    }
+}
+
+/// Data written before the running policy existed, or while it was set to
+/// parallel, can have several tasks running. Unless tasks may run in
+/// parallel, keeps the most recently started one and ends the others when it
+/// started, so no time is counted twice.
+fn repair_running_tasks(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    policy: RunningPolicy,
+) -> Result<(), String> {
+    let running = tasks_with_status(time_period, TaskStatus::Running);
+    let Some(newest) = running.first() else {
+        return Ok(());
+    };
+    let displaced = displaced_tasks(time_period, policy, Some(newest));
+    if displaced.is_empty() {
+        return Ok(());
+    }
+    let started = find_task(time_period, &newest.0, &newest.1)
+        .and_then(|task| task.time_chunks.last())
+        .map(|chunk| chunk.start_time)
+        .unwrap();
+    eprintln!(
+        "Found {} running tasks; only {:?} ({}) keeps running.",
+        running.len(),
+        newest.1,
+        newest.0
+    );
+    for other in &displaced {
+        record(time_period, journal, other.action(started))?;
+        eprintln!(
+            "{} {:?} ({}) at {}",
+            if other.paused { "Paused" } else { "Stopped" },
+            other.task,
+            other.category,
+            started.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+    eprintln!("Use `tt configure --running-policy parallel` to allow overlapping tasks.");
+    Ok(())
+}
+
+/// The task `reference` names, which must be one of `candidates`, or else
+/// the only candidate. Refuses to guess when there are several.
+fn pick_task(
+    time_period: &TimePeriod,
+    reference: Option<String>,
+    candidates: &[(String, String)],
+    what: &str,
+) -> Result<Option<(String, String)>, String> {
+    match reference {
+        Some(reference) => {
+            let found = resolve_task(time_period, &reference)?;
+            if candidates.contains(&found) {
+                Ok(Some(found))
+            } else {
+                Err(format!("{:?} is not {}", found.1, what))
+            }
+        }
+        None if candidates.len() > 1 => Err(format!(
+            "Several tasks are {} ({}); say which one",
+            what,
+            candidates
+                .iter()
+                .map(|(_, name)| format!("{:?}", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+        None => Ok(candidates.first().cloned()),
+    }
+}
+
+fn stop_task(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    task: Option<String>,
+) -> Result<(), String> {
+    let running = tasks_with_status(time_period, TaskStatus::Running);
+    let paused = tasks_with_status(time_period, TaskStatus::Paused);
+    // A paused task is only stopped when named.
+    let found = match &task {
+        Some(_) => {
+            let active: Vec<(String, String)> = running.into_iter().chain(paused.clone()).collect();
+            pick_task(time_period, task, &active, "running or paused")?
+        }
+        None => pick_task(time_period, None, &running, "running")?,
+    };
+    if let Some((category, task)) = found {
+        let at = Utc::now();
+        let stopped = format!("Stopped current task: {:?}", task);
+        record(time_period, journal, Action::Stop { category, task, at })?;
+        println!("{}", stopped);
+    } else if let Some((_, task)) = paused.first() {
+        println!(
+            "No task is currently running. {:?} is paused; use `tt stop {}` to stop it.",
+            task, task
+        );
+    } else {
+        println!("No task is currently running.");
+    }
+    Ok(())
+}
+
+fn pause_task(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    task: Option<String>,
+) -> Result<(), String> {
+    let running = tasks_with_status(time_period, TaskStatus::Running);
+    if let Some((category, task)) = pick_task(time_period, task, &running, "running")? {
+        let at = Utc::now();
+        let paused = format!("Paused current task: {:?}", task);
+        record(time_period, journal, Action::Pause { category, task, at })?;
+        println!("{}", paused);
+    } else {
+        println!("No task is currently running.");
+    }
+    Ok(())
+}
+
+fn resume_task(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    task: Option<String>,
+    policy: RunningPolicy,
+) -> Result<(), String> {
+    let paused = tasks_with_status(time_period, TaskStatus::Paused);
+    // Without a name, asks which paused task to resume, falling back to the
+    // most recently paused one.
+    let task = match task {
+        None if paused.len() > 1 => {
+            ask_for_task(time_period, "Which task do you want to resume?", |task| {
+                task.status == TaskStatus::Paused
+            })
+        }
+        task => task,
+    };
+    let paused = match task {
+        Some(_) => pick_task(time_period, task, &paused, "paused")?,
+        None => paused.into_iter().next(),
+    };
+    if let Some((category, task)) = paused {
+        let at = Utc::now();
+        let displaced = displaced_tasks(time_period, policy, None);
+        let resumed = format!("Resumed task: {:?}", task);
+        record(
+            time_period,
+            journal,
+            Action::Resume {
+                category,
+                task,
+                at,
+                chunk_id: data::new_id(),
+                displaced: displaced.clone(),
+            },
+        )?;
+        report_displaced(&displaced);
+        println!("{}", resumed);
     } else {
         println!("No paused task found.");
     }
+    Ok(())
+}
+
+/// Prints the status from `template`, or as JSON without one. Falls back to
+/// loading the data when the status file is missing or out of date.
+fn print_status_line(app_config: &AppConfig, template: Option<&str>) {
+    let path = app_config.storage_path();
+    let status_file = status_path(&path);
+    let snapshot = status::Snapshot::read(&status_file, &path)
+        .unwrap_or_else(|| refresh_status(app_config, &status_file));
+    let status = snapshot.status(Utc::now());
+    match template {
+        Some(template) => println!("{}", status.render(template)),
+        None => println!("{}", status.to_json()),
+    }
+}
+
+/// Works the status out from the data and writes it down, so the next poll
+/// reads the status file again. While another command holds the lock, the
+/// last status written stands in rather than waiting or failing.
+fn refresh_status(app_config: &AppConfig, status_file: &Path) -> status::Snapshot {
+    let now = Utc::now();
+    let Ok(_lock) = storage::lock(&app_config.storage_path()) else {
+        return status::Snapshot::read_last(status_file).unwrap_or_else(|| {
+            let nothing = TimePeriod {
+                categorization: Categorization::new(),
+            };
+            status::Snapshot::of(&nothing, now)
+        });
+    };
+    let mut storage = exit_on_error(storage::open(app_config));
+    let (_, time_period) = exit_on_error(Journal::open(storage.as_mut()));
+    let snapshot = status::Snapshot::of(&time_period, now);
+    // The status line is still worth printing if the file cannot be written.
+    let _ = snapshot.write(status_file);
+    snapshot
 }
 
 fn clock(time_period: &mut TimePeriod) {
+    let short_ids = time_period.short_ids();
     for tasks in time_period.categorization.categories.values_mut() {
         for task in tasks {
-            if let data::TaskStatus::Running = task.status {
-                task.time_spent();
-                break;
+            match task.status {
+                data::TaskStatus::Running => {
+                    println!("{} {}", short_ids[&task.id], task.name);
+                    task.time_spent();
+                    print_notes(task);
+                }
+                data::TaskStatus::Paused => {
+                    println!(
+                        "Paused: {} {} (paused for {})",
+                        short_ids[&task.id],
+                        task.name,
+                        format_duration(task.paused(Utc::now()))
+                    );
+                    task.time_spent();
+                    print_notes(task);
+                }
+                data::TaskStatus::Stopped => {}
             }
         }
     }
 }
 
-fn list_tasks(time_period: &TimePeriod) {
+fn list_tasks(time_period: &TimePeriod, tags: &BTreeSet<String>) {
     println!("Listing tasks");
+    let short_ids = time_period.short_ids();
     for (category, tasks) in &time_period.categorization.categories {
+        let tasks: Vec<&Task> = tasks
+            .iter()
+            .filter(|task| task.all_tags(category).is_superset(tags))
+            .collect();
+        if tasks.is_empty() && !tags.is_empty() {
+            continue;
+        }
         println!("Category: {:?}", category);
         for task in tasks {
             println!(
-                "{} - {} - {}",
+                "{} {} - {} - {}",
+                short_ids[&task.id],
                 task.name,
                 task.time_chunks[0].start_time.to_rfc3339(),
                 task.time_chunks
@@ -231,61 +2171,673 @@ fn list_tasks(time_period: &TimePeriod) {
                     .unwrap_or_else(|| String::from("N/A")),
             );
             task.time_spent();
+            if !task.tags.is_empty() {
+                println!("Tags: {}", format_tags(&task.tags));
+            }
+            print_notes(task);
+            if !task.pauses.is_empty() {
+                println!("Paused for {}", format_duration(task.paused(Utc::now())));
+            }
+        }
+    }
+}
+
+/// What to import from where: `--from <source> <path>` or `<source> <path>`.
+fn import_source(
+    from: Option<ImportSource>,
+    args: &[String],
+) -> Result<(ImportSource, PathBuf), String> {
+    match (from, args) {
+        (Some(source), [path]) => Ok((source, PathBuf::from(path))),
+        (None, [source, path]) => Ok((source.parse()?, PathBuf::from(path))),
+        _ => Err(String::from(
+            "Give the source and what to read, e.g. `tt import watson frames` or `tt import --from toggl export.csv`",
+        )),
+    }
+}
+
+/// Adds imported entries as one change, after listing them. Entries that
+/// `imported_before` finds in their task are skipped.
+fn import_entries(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    source: ImportSource,
+    entries: Vec<integration::Entry>,
+    dry_run: bool,
+    yes: bool,
+) -> Result<(), String> {
+    let mut edited = time_period.clone();
+    let mut added = Vec::new();
+    let mut created = BTreeSet::new();
+    let mut claimed = HashSet::new();
+    let mut skipped = 0;
+    for entry in entries {
+        let tasks = edited
+            .categorization
+            .categories
+            .entry(entry.category.clone())
+            .or_default();
+        let index = match tasks.iter().position(|task| task.name == entry.name) {
+            Some(index) => index,
+            None => {
+                let mut task = Task::from_chunks(&entry.name, Vec::new());
+                task.tags = entry.tags.clone();
+                created.insert(task.id.clone());
+                tasks.push(task);
+                tasks.len() - 1
+            }
+        };
+        let task = &mut tasks[index];
+        if let Some(chunk_id) = imported_before(task, &entry, source, &claimed) {
+            claimed.insert(chunk_id);
+            skipped += 1;
+            continue;
         }
+        let mut chunk = TimeChunk::new(entry.start, Some(entry.end));
+        chunk.notes = entry
+            .notes
+            .iter()
+            .map(|text| Note {
+                at: entry.start,
+                text: text.clone(),
+            })
+            .collect();
+        claimed.insert(chunk.id.clone());
+        task.time_chunks.push(chunk);
+        task.time_chunks.sort_by_key(|chunk| chunk.start_time);
+        task.tags.extend(entry.tags.iter().cloned());
+        added.push(entry);
+    }
+    // Tasks created for duplicates only are not part of the change.
+    for tasks in edited.categorization.categories.values_mut() {
+        tasks.retain(|task| !created.contains(&task.id) || !task.time_chunks.is_empty());
+    }
+    edited.categorization.categories.retain(|category, tasks| {
+        !tasks.is_empty() || time_period.categorization.categories.contains_key(category)
+    });
+
+    let total = added.iter().fold(chrono::Duration::zero(), |acc, entry| {
+        acc + (entry.end - entry.start)
+    });
+    for entry in &added {
+        let line = format!(
+            "+ {} - {} {} ({}) {}",
+            entry.start.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            entry.end.with_timezone(&Local).format("%H:%M"),
+            entry.name,
+            entry.category,
+            format_tags(&entry.tags)
+        );
+        println!("{}", line.trim_end());
+    }
+    if skipped > 0 {
+        println!("Skipping {} entries that were already imported.", skipped);
+    }
+    if added.is_empty() {
+        println!("Nothing to import.");
+        return Ok(());
+    }
+    println!(
+        "{} new entries, {} in total.",
+        added.len(),
+        format_duration(total)
+    );
+    if dry_run {
+        return Ok(());
     }
+    if !yes && !utils::confirm(&format!("Import {} entries?", added.len())) {
+        println!("Nothing imported. Pass --yes to import without asking.");
+        return Ok(());
+    }
+    record_changes(time_period, journal, &edited)?;
+    println!("Imported {} entries.", added.len());
+    Ok(())
 }
 
-fn export_data(file_path: PathBuf) {
-    println!("Exporting data to {:?}", file_path);
-    // Implement functionality in the appropriate module
+/// The chunk of `task` that `entry` was imported as before, if any. Most
+/// sources record when an entry started and ended. Harvest only records hours
+/// per day, and `read` makes up the times from the order of the rows, so its
+/// entries match a chunk on the same day with the same length and notes that
+/// this import has not matched or added yet.
+fn imported_before(
+    task: &Task,
+    entry: &integration::Entry,
+    source: ImportSource,
+    claimed: &HashSet<String>,
+) -> Option<String> {
+    let found = match source {
+        ImportSource::Harvest => {
+            let day = entry.start.with_timezone(&Local).date_naive();
+            task.time_chunks.iter().find(|chunk| {
+                !claimed.contains(&chunk.id)
+                    && chunk.start_time.with_timezone(&Local).date_naive() == day
+                    && chunk.end_time == Some(chunk.start_time + (entry.end - entry.start))
+                    && chunk
+                        .notes
+                        .iter()
+                        .map(|note| &note.text)
+                        .eq(entry.notes.iter())
+                    && task.tags.is_superset(&entry.tags)
+            })
+        }
+        ImportSource::Toggl
+        | ImportSource::Clockify
+        | ImportSource::Timewarrior
+        | ImportSource::Watson
+        | ImportSource::Ics
+        | ImportSource::Timeclock => task
+            .time_chunks
+            .iter()
+            .find(|chunk| chunk.start_time == entry.start && chunk.end_time == Some(entry.end)),
+    };
+    found.map(|chunk| chunk.id.clone())
 }
 
-fn generate_summary(time_period: &TimePeriod, period: String, category: Option<String>) {
+fn export_data(
+    time_period: &TimePeriod,
+    format: ExportFormat,
+    from: Option<String>,
+    to: Option<String>,
+    category: Option<String>,
+    file_path: Option<PathBuf>,
+) -> Result<(), String> {
+    let now = Local::now();
+    let point = |input: Option<String>| {
+        input
+            .map(|input| utils::parse_datetime(&input, now))
+            .transpose()
+    };
+    let filter = ExportFilter {
+        from: point(from)?,
+        to: point(to)?,
+        category: category.as_deref().map(normalize_category),
+    };
+    let rows = export::rows(time_period, &filter, now.with_timezone(&Utc));
+    if let (ExportFormat::Timewarrior, Some(dir)) =
+        (format, file_path.as_ref().filter(|path| path.is_dir()))
+    {
+        let files = export::write_timewarrior_dir(&rows, dir)
+            .map_err(|e| format!("Could not export: {}", e))?;
+        println!(
+            "Exported {} chunks to {} files in {:?}",
+            rows.len(),
+            files,
+            dir
+        );
+        return Ok(());
+    }
+    let written = match &file_path {
+        Some(path) => std::fs::File::create(path)
+            .map_err(|e| e.into())
+            .and_then(|mut file| export::write_rows(&rows, format, &mut file)),
+        None => export::write_rows(&rows, format, &mut std::io::stdout().lock()),
+    };
+    written.map_err(|e| format!("Could not export: {}", e))?;
+    if let Some(path) = file_path {
+        println!("Exported {} chunks to {:?}", rows.len(), path);
+    }
+    Ok(())
+}
+
+fn generate_summary(
+    time_period: &TimePeriod,
+    period: String,
+    category: Option<String>,
+    tags: &BTreeSet<String>,
+    depth: Option<usize>,
+) {
     let time_period = &time_period.categorization.categories;
     match period.to_lowercase().as_str() {
         "day" => {
             // Summary for the last day
             println!("Time spent in the last day: ");
-            print_summary_with_duration(time_period, chrono::Duration::days(1), category);
+            print_summary_with_duration(
+                time_period,
+                chrono::Duration::days(1),
+                category,
+                tags,
+                depth,
+            );
         }
         "week" => {
             // Summary for the last week
             println!("Time spent in the last week: ");
-            print_summary_with_duration(time_period, chrono::Duration::weeks(1), category);
+            print_summary_with_duration(
+                time_period,
+                chrono::Duration::weeks(1),
+                category,
+                tags,
+                depth,
+            );
         }
         "month" => {
             // Summary for the last month
             println!("Time spent in the last month: ");
-            print_summary_with_duration(time_period, chrono::Duration::days(30), category);
+            print_summary_with_duration(
+                time_period,
+                chrono::Duration::days(30),
+                category,
+                tags,
+                depth,
+            );
         }
         x => {
             let period = x.parse().unwrap_or(1);
-            print_summary_with_duration(time_period, chrono::Duration::days(period), category)
+            print_summary_with_duration(
+                time_period,
+                chrono::Duration::days(period),
+                category,
+                tags,
+                depth,
+            )
         }
     }
 }
 
-fn configure_app(storage_location: Option<PathBuf>) {
-    println!(
-        "Configuring app with storage location: {:?}",
-        storage_location
-    );
+fn configure_app(
+    storage_location: Option<PathBuf>,
+    backend: Option<StorageBackend>,
+    backup_retention: BackupRetention,
+    running_policy: Option<RunningPolicy>,
+) -> Result<(), String> {
     let mut app_config = AppConfig::load();
-    app_config.storage_location = storage_location;
-    app_config.save().unwrap();
+    if let Some(running_policy) = running_policy {
+        println!("Configuring app with running policy: {:?}", running_policy);
+        app_config.running_policy = running_policy;
+    }
+    if backup_retention != app_config.backup_retention {
+        println!(
+            "Configuring app with backup retention: {:?}",
+            backup_retention
+        );
+        app_config.backup_retention = backup_retention;
+    }
+    let previous_data = storage::open(&app_config)
+        .and_then(|storage| Ok((storage.load()?, storage.load_events()?)));
+
+    if let Some(backend) = backend {
+        println!("Configuring app with storage backend: {:?}", backend);
+        app_config.storage_backend = backend;
+        app_config.storage_location = app_config
+            .storage_location
+            .map(|location| location.with_file_name(backend.file_name()));
+    }
+    if let Some(storage_location) = storage_location {
+        let storage_location = storage_location.join(app_config.storage_backend.file_name());
+        println!(
+            "Configuring app with storage location: {:?}",
+            storage_location
+        );
+        app_config.storage_location = Some(storage_location);
+    }
+
+    // Carry existing tasks and their history over when the new location or
+    // backend starts out empty
+    if let Ok((previous_data, previous_events)) = previous_data {
+        storage::open(&app_config)
+            .and_then(|mut new_storage| {
+                if new_storage.load_events()?.is_empty()
+                    && new_storage.load()?.categorization.categories.is_empty()
+                {
+                    for event in &previous_events {
+                        new_storage.append_event(event)?;
+                    }
+                    new_storage.save(&previous_data)?;
+                }
+                Ok(())
+            })
+            .map_err(|e| format!("Could not move the data over: {}", e))?;
+    }
+    app_config
+        .save()
+        .map_err(|e| format!("Could not save the configuration: {}", e))
+}
+
+fn visualize(
+    time_period: &TimePeriod,
+    output: &PathBuf,
+    category: Option<&str>,
+    depth: Option<usize>,
+) -> Result<(), String> {
+    visualize_data(time_period, output, category, depth)
+        .map_err(|e| format!("Could not draw the chart: {}", e))?;
+    println!("Wrote the chart to {:?}", output);
+    Ok(())
+}
+
+fn clear(time_period: &mut TimePeriod, journal: &mut Journal) -> Result<(), String> {
+    record(time_period, journal, Action::Clear)?;
+    println!("Cleared all data");
+    Ok(())
 }
 
-fn visualize(time_period: &TimePeriod) {
-    println!("Visualizing time tracking data");
-    visualize_data(
+/// Clears a pending recovery, either by restoring `restore` (after checking
+/// that it parses) or by starting with empty data. Either way the change is
+/// recorded, so the event log agrees with the data file.
+fn recover(
+    app_config: &AppConfig,
+    restore: Option<&Path>,
+    start_fresh: bool,
+) -> Result<(), String> {
+    if restore.is_none() && !start_fresh {
+        return Err(String::from(
+            "Pass --restore <file> or --start-fresh to confirm recovery.",
+        ));
+    }
+    let path = app_config.storage_path();
+    if !data::recovery_pending(&path) {
+        println!("No recovery pending for {:?}", path);
+        return Ok(());
+    }
+    let restored = restore
+        .map(data::read_data_file)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    data::clear_recovery(&path).map_err(|e| e.to_string())?;
+    let mut storage = storage::open(app_config).map_err(|e| e.to_string())?;
+    let (mut journal, mut time_period) =
+        Journal::open(storage.as_mut()).map_err(|e| e.to_string())?;
+    let action = match restored {
+        Some(restored) => Action::Snapshot {
+            categorization: restored.categorization,
+        },
+        None => Action::Clear,
+    };
+    journal
+        .record(&mut time_period, action)
+        .map_err(|e| e.to_string())?;
+    match restore {
+        Some(source) => println!("Restored data from {:?}", source),
+        None => println!("Starting with empty data at {:?}", path),
+    }
+    Ok(())
+}
+
+fn describe_data(time_period: &TimePeriod, at: DateTime<Utc>) -> String {
+    format!(
+        "{} tasks, {} tracked",
+        time_period.task_count(),
+        format_duration(time_period.total_tracked(at))
+    )
+}
+
+fn list_backups(backup_dir: &Path) -> Result<(), String> {
+    let snapshots = backup::list_snapshots(backup_dir)
+        .map_err(|e| format!("Could not list the backups in {:?}: {}", backup_dir, e))?;
+    if snapshots.is_empty() {
+        println!("No backups in {:?}", backup_dir);
+    }
+    for snapshot in snapshots {
+        let summary = match backup::read_snapshot(&snapshot) {
+            Ok(time_period) => describe_data(&time_period, snapshot.taken_at),
+            Err(e) => format!("unreadable: {}", e),
+        };
+        println!(
+            "{}  {}  {}",
+            snapshot.id,
+            snapshot
+                .taken_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S"),
+            summary
+        );
+    }
+    Ok(())
+}
+
+fn restore_backup(
+    time_period: &mut TimePeriod,
+    journal: &mut Journal,
+    backup_dir: &Path,
+    id: &str,
+    yes: bool,
+) -> Result<(), String> {
+    let (snapshot, restored) = backup::find_snapshot(backup_dir, id)
+        .and_then(|snapshot| backup::read_snapshot(&snapshot).map(|data| (snapshot, data)))
+        .map_err(|e| e.to_string())?;
+
+    println!(
+        "Backup {} from {}: {}",
+        snapshot.id,
+        snapshot
+            .taken_at
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S"),
+        describe_data(&restored, snapshot.taken_at)
+    );
+    println!("Current data: {}", describe_data(time_period, Utc::now()));
+    if !yes && !utils::confirm("Replace the current data with this backup?") {
+        println!("Restore cancelled");
+        return Ok(());
+    }
+
+    record(
         time_period,
-        &PathBuf::from("/Users/lowband/dev/rust/time_tracker/chart.png"),
+        journal,
+        Action::Snapshot {
+            categorization: restored.categorization,
+        },
+    )?;
+    println!("Restored backup {}", snapshot.id);
+    Ok(())
+}
+
+fn show_log(journal: &Journal, limit: usize) {
+    let events = journal.events();
+    for event in &events[events.len().saturating_sub(limit)..] {
+        println!(
+            "#{} {} {} {}",
+            event.id,
+            event
+                .timestamp
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S"),
+            event.user,
+            event.action
+        );
+    }
+}
+
+/// What was running or paused at `when`, from the chunks and pauses as they
+/// are now, so time added or edited afterwards counts too.
+fn show_at(time_period: &TimePeriod, when: &str) -> Result<(), String> {
+    let at = utils::parse_datetime(when, Local::now())?;
+    let local_at = at.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+    let covers =
+        |chunk: &&TimeChunk| chunk.start_time <= at && chunk.end_time.is_none_or(|end| at < end);
+
+    let mut found = false;
+    for (category, tasks) in sorted_categories(time_period) {
+        for task in tasks {
+            if let Some(chunk) = task.time_chunks.iter().find(covers) {
+                found = true;
+                println!(
+                    "At {}: running {:?} ({}) since {}",
+                    local_at,
+                    task.name,
+                    category,
+                    chunk
+                        .start_time
+                        .with_timezone(&Local)
+                        .format("%Y-%m-%d %H:%M")
+                );
+            } else if task.pauses.iter().any(|pause| covers(&pause)) {
+                found = true;
+                println!("At {}: paused {:?} ({})", local_at, task.name, category);
+            }
+        }
+    }
+    if !found {
+        println!("At {}: nothing was running", local_at);
+    }
+    Ok(())
+}
+
+fn undo(time_period: &mut TimePeriod, journal: &mut Journal, count: usize) -> Result<(), String> {
+    for _ in 0..count {
+        match journal.undo(time_period).map_err(|e| e.to_string())? {
+            Some(event) => println!("Undid #{}: {}", event.id, event.action),
+            None => {
+                println!("Nothing left to undo.");
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn redo(time_period: &mut TimePeriod, journal: &mut Journal, count: usize) -> Result<(), String> {
+    for _ in 0..count {
+        match journal.redo(time_period).map_err(|e| e.to_string())? {
+            Some(event) => println!("Redid #{}: {}", event.id, event.action),
+            None => {
+                println!("Nothing left to redo.");
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn describe_task(task: &Task, at: DateTime<Utc>) -> String {
+    format!(
+        "{:?}, {} chunks, {}",
+        task.status,
+        task.time_chunks.len(),
+        format_duration(task.tracked(at))
     )
-    .unwrap();
 }
 
-fn clear(time_period: &mut TimePeriod, storage_location: &PathBuf) {
-    time_period.categorization = Categorization::new();
-    save_data(storage_location, &time_period).unwrap();
-    println!("Cleared all data");
+fn show_history(journal: &Journal, limit: usize) {
+    for entry in events::history(journal.events(), limit) {
+        let event = entry.event;
+        println!(
+            "#{} {} {} {}{}",
+            event.id,
+            event
+                .timestamp
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S"),
+            event.user,
+            event.action,
+            if entry.undone { " (undone)" } else { "" }
+        );
+        match entry.effect {
+            Effect::Task {
+                category,
+                before,
+                after,
+            } => {
+                let name = after
+                    .as_ref()
+                    .or(before.as_ref())
+                    .map(|task| task.name.clone());
+                let describe = |task: Option<Box<Task>>| {
+                    task.map_or(String::from("none"), |task| {
+                        describe_task(&task, event.timestamp)
+                    })
+                };
+                println!(
+                    "    {:?} ({}): {} -> {}",
+                    name.unwrap_or_default(),
+                    category,
+                    describe(before),
+                    describe(after)
+                );
+            }
+            Effect::TaskCount { before, after } => {
+                println!("    {} tasks -> {} tasks", before, after)
+            }
+            Effect::Nothing => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    /// A stopped task with an hour-long chunk starting each of `days_ago`.
+    fn task(name: &str, days_ago: &[i64]) -> Task {
+        let now = Utc::now();
+        let chunks = days_ago
+            .iter()
+            .map(|days| {
+                let start = now - Duration::days(*days);
+                TimeChunk::new(start, Some(start + Duration::hours(1)))
+            })
+            .collect();
+        Task::from_chunks(name, chunks)
+    }
+
+    fn time_period(categories: Vec<(&str, Vec<Task>)>) -> TimePeriod {
+        let mut categorization = Categorization::new();
+        for (category, tasks) in categories {
+            categorization
+                .categories
+                .insert(category.to_string(), tasks);
+        }
+        TimePeriod { categorization }
+    }
+
+    fn names(tasks: Vec<(&String, &Task)>) -> Vec<String> {
+        tasks
+            .into_iter()
+            .map(|(category, task)| task_label(category, task))
+            .collect()
+    }
+
+    #[test]
+    fn recent_tasks_puts_the_most_recent_first() {
+        let time_period = time_period(vec![
+            ("#acme", vec![task("old", &[30]), task("today", &[0])]),
+            ("Uncategorized", vec![task("last week", &[7])]),
+        ]);
+
+        assert_eq!(
+            names(recent_tasks(&time_period, |_| true)),
+            ["today (#acme)", "last week (Uncategorized)", "old (#acme)"]
+        );
+    }
+
+    #[test]
+    fn recent_tasks_favours_frequent_use_over_a_single_recent_chunk() {
+        let time_period = time_period(vec![(
+            "#acme",
+            vec![task("once", &[1]), task("daily", &[2, 3, 4, 5, 6])],
+        )]);
+
+        assert_eq!(
+            names(recent_tasks(&time_period, |_| true)),
+            ["daily (#acme)", "once (#acme)"]
+        );
+    }
+
+    #[test]
+    fn recent_tasks_breaks_ties_by_category_then_name() {
+        let time_period = time_period(vec![
+            ("#web", vec![task("b", &[]), task("a", &[])]),
+            ("#acme", vec![task("z", &[])]),
+        ]);
+
+        assert_eq!(
+            names(recent_tasks(&time_period, |_| true)),
+            ["z (#acme)", "a (#web)", "b (#web)"]
+        );
+    }
+
+    #[test]
+    fn recent_tasks_leaves_out_unwanted_tasks() {
+        let mut running = task("running", &[0]);
+        running.status = TaskStatus::Running;
+        let time_period = time_period(vec![("#acme", vec![running, task("stopped", &[9])])]);
+
+        assert_eq!(
+            names(recent_tasks(&time_period, |task| {
+                task.status != TaskStatus::Running
+            })),
+            ["stopped (#acme)"]
+        );
+    }
 }
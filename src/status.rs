@@ -0,0 +1,272 @@
+// src/status.rs
+use crate::data::{sibling_path, TaskStatus, TimePeriod};
+use crate::events::Event;
+use crate::storage::Storage;
+use crate::utils;
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The status file lives beside the data file, e.g. `tasks.json.status`.
+pub fn status_path(storage_path: &Path) -> PathBuf {
+    sibling_path(storage_path, "status")
+}
+
+/// Just enough of the data to tell what is going on at any later moment,
+/// small enough to be read every second by a shell prompt or status bar.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The local day `finished_today` was counted on.
+    date: NaiveDate,
+    /// Seconds tracked today in chunks that had already ended.
+    finished_today: i64,
+    /// Running tasks, most recently started first, then paused tasks, most
+    /// recently paused first.
+    active: Vec<Active>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Active {
+    name: String,
+    category: String,
+    tags: BTreeSet<String>,
+    status: TaskStatus,
+    /// When the task last started running or was paused.
+    since: DateTime<Utc>,
+    /// Seconds tracked in chunks that had already ended.
+    finished: i64,
+}
+
+/// What `tt status --format` and `--json` show.
+pub struct Status<'a> {
+    active: Option<&'a Active>,
+    /// Time spent in the current state, running or paused.
+    elapsed: Duration,
+    /// Time the task has tracked in total.
+    total: Duration,
+    /// Time tracked today across all tasks.
+    today: Duration,
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    utils::local_to_utc(date.and_hms_opt(0, 0, 0).unwrap()).unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+impl Snapshot {
+    pub fn of(time_period: &TimePeriod, now: DateTime<Utc>) -> Self {
+        let date = now.with_timezone(&Local).date_naive();
+        let (midnight, next_midnight) = (
+            start_of_day(date),
+            start_of_day(date + chrono::Days::new(1)),
+        );
+        let mut finished_today = Duration::zero();
+        let mut active = Vec::new();
+        for (category, tasks) in &time_period.categorization.categories {
+            for task in tasks {
+                let ended = task
+                    .time_chunks
+                    .iter()
+                    .filter(|chunk| chunk.end_time.is_some());
+                for chunk in ended.clone() {
+                    finished_today =
+                        finished_today + chunk.duration_within(midnight, next_midnight, now);
+                }
+                let since = match task.status {
+                    TaskStatus::Running => task.time_chunks.last(),
+                    TaskStatus::Paused => task.pauses.last(),
+                    TaskStatus::Stopped => None,
+                };
+                if let Some(since) = since {
+                    active.push(Active {
+                        name: task.name.clone(),
+                        category: category.clone(),
+                        tags: task.tags.clone(),
+                        status: task.status.clone(),
+                        since: since.start_time,
+                        finished: ended.map(|chunk| chunk.duration(now).num_seconds()).sum(),
+                    });
+                }
+            }
+        }
+        active.sort_by(|a, b| {
+            (a.status != TaskStatus::Running)
+                .cmp(&(b.status != TaskStatus::Running))
+                .then(b.since.cmp(&a.since))
+        });
+        Snapshot {
+            date,
+            finished_today: finished_today.num_seconds(),
+            active,
+        }
+    }
+
+    /// Reads the status file at `path`, unless it is missing, unreadable or
+    /// older than the data file at `storage_path`. Without a data file there
+    /// is nothing it could be older than.
+    pub fn read(path: &Path, storage_path: &Path) -> Option<Self> {
+        let written = fs::metadata(path).ok()?.modified().ok()?;
+        let saved = fs::metadata(storage_path).and_then(|data| data.modified());
+        if saved.is_ok_and(|saved| written < saved) {
+            return None;
+        }
+        Self::read_last(path)
+    }
+
+    /// Reads the status file at `path` however old it is.
+    pub fn read_last(path: &Path) -> Option<Self> {
+        serde_json::from_slice(&fs::read(path).ok()?).ok()
+    }
+
+    /// Replaces the status file at `path` in one step, so a reader never sees
+    /// half of it.
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let partial = sibling_path(path, "tmp");
+        fs::write(&partial, serde_json::to_vec(self)?)?;
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    pub fn status(&self, now: DateTime<Utc>) -> Status<'_> {
+        let today = now.with_timezone(&Local).date_naive();
+        let midnight = start_of_day(today);
+        // Chunks that ended before today no longer count.
+        let mut tracked_today = if self.date == today {
+            Duration::seconds(self.finished_today)
+        } else {
+            Duration::zero()
+        };
+        for running in self
+            .active
+            .iter()
+            .filter(|active| active.status == TaskStatus::Running)
+        {
+            tracked_today = tracked_today + (now - running.since.max(midnight));
+        }
+
+        let active = self.active.first();
+        let elapsed = active.map_or_else(Duration::zero, |active| now - active.since);
+        let total = active.map_or_else(Duration::zero, |active| match active.status {
+            TaskStatus::Running => Duration::seconds(active.finished) + elapsed,
+            _ => Duration::seconds(active.finished),
+        });
+        Status {
+            active,
+            elapsed,
+            total,
+            today: tracked_today,
+        }
+    }
+}
+
+/// `HH:MM:SS`, as `tt status` has always printed it.
+fn clock_time(duration: Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+impl Status<'_> {
+    fn state(&self) -> &'static str {
+        match self.active.map(|active| &active.status) {
+            Some(TaskStatus::Running) => "running",
+            Some(TaskStatus::Paused) => "paused",
+            _ => "idle",
+        }
+    }
+
+    /// Fills in `{name}`, `{category}`, `{tags}`, `{status}`, `{elapsed}`,
+    /// `{total}` and `{today}`. When idle, the task placeholders are empty.
+    pub fn render(&self, template: &str) -> String {
+        let (name, category, tags) = match self.active {
+            Some(active) => (
+                active.name.as_str(),
+                active.category.as_str(),
+                active
+                    .tags
+                    .iter()
+                    .map(|tag| format!("#{}", tag))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            None => ("", "", String::new()),
+        };
+        template
+            .replace("{name}", name)
+            .replace("{category}", category)
+            .replace("{tags}", &tags)
+            .replace("{status}", self.state())
+            .replace("{elapsed}", &clock_time(self.elapsed))
+            .replace("{total}", &clock_time(self.total))
+            .replace("{today}", &clock_time(self.today))
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "status": self.state(),
+            "name": self.active.map(|active| &active.name),
+            "category": self.active.map(|active| &active.category),
+            "tags": self.active.map_or_else(Vec::new, |active| active.tags.iter().collect()),
+            "elapsed": clock_time(self.elapsed),
+            "elapsed_seconds": self.elapsed.num_seconds(),
+            "total": clock_time(self.total),
+            "total_seconds": self.total.num_seconds(),
+            "today": clock_time(self.today),
+            "today_seconds": self.today.num_seconds(),
+        })
+    }
+}
+
+/// Storage wrapper that rewrites the status file after every successful
+/// write.
+pub struct StatusStorage {
+    inner: Box<dyn Storage>,
+    path: PathBuf,
+}
+
+impl StatusStorage {
+    pub fn new(inner: Box<dyn Storage>, path: PathBuf) -> Self {
+        Self { inner, path }
+    }
+}
+
+impl Storage for StatusStorage {
+    fn load(&self) -> Result<TimePeriod, Box<dyn Error>> {
+        self.inner.load()
+    }
+
+    fn load_events(&self) -> Result<Vec<Event>, Box<dyn Error>> {
+        self.inner.load_events()
+    }
+
+    fn append_event(&mut self, event: &Event) -> Result<(), Box<dyn Error>> {
+        self.inner.append_event(event)
+    }
+
+    fn save(&mut self, time_period: &TimePeriod) -> Result<(), Box<dyn Error>> {
+        self.inner.save(time_period)?;
+        Snapshot::of(time_period, Utc::now()).write(&self.path)
+    }
+
+    fn save_task(
+        &mut self,
+        time_period: &TimePeriod,
+        category: &str,
+        index: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        self.inner.save_task(time_period, category, index)?;
+        Snapshot::of(time_period, Utc::now()).write(&self.path)
+    }
+
+    fn rebuild(&mut self, time_period: &TimePeriod) -> Result<(), Box<dyn Error>> {
+        self.inner.rebuild(time_period)?;
+        Snapshot::of(time_period, Utc::now()).write(&self.path)
+    }
+}
@@ -0,0 +1,509 @@
+// src/storage.rs
+use crate::backup::{self, BackedUpStorage};
+use crate::categorization::Categorization;
+use crate::config::{AppConfig, StorageBackend};
+use crate::data::{
+    clear_recovery, load_data, save_data, sibling_path, DataError, Task, TaskStatus, TimeChunk,
+    TimePeriod,
+};
+use crate::events::Event;
+use crate::status::{status_path, StatusStorage};
+use fs2::FileExt;
+use rusqlite::types::Type;
+use rusqlite::{params, Connection};
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub fn open(config: &AppConfig) -> Result<Box<dyn Storage>, Box<dyn Error>> {
+    let path = config.storage_path();
+    let mut storage: Box<dyn Storage> = match config.storage_backend {
+        StorageBackend::Json => Box::new(JsonStorage::new(path.clone())),
+        StorageBackend::Sqlite => Box::new(SqliteStorage::open(&path)?),
+    };
+    if !config.backup_retention.is_disabled() {
+        storage = Box::new(BackedUpStorage::new(
+            storage,
+            backup::backup_dir(&path),
+            config.backup_retention.clone(),
+        ));
+    }
+    Ok(Box::new(StatusStorage::new(storage, status_path(&path))))
+}
+
+/// Exclusive advisory lock on a storage location, released on drop.
+pub struct StorageLock {
+    _file: File,
+}
+
+/// Locks `path` for the whole load-modify-save cycle of one command, failing
+/// immediately if another process already holds it.
+pub fn lock(path: &Path) -> Result<StorageLock, Box<dyn Error>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let lock_path = sibling_path(path, "lock");
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)?;
+    file.try_lock_exclusive().map_err(|_| {
+        format!(
+            "{:?} is locked by another time_tracker process; try again once it finishes",
+            path
+        )
+    })?;
+    Ok(StorageLock { _file: file })
+}
+
+pub trait Storage {
+    fn load(&self) -> Result<TimePeriod, Box<dyn Error>>;
+
+    fn save(&mut self, time_period: &TimePeriod) -> Result<(), Box<dyn Error>>;
+
+    fn load_events(&self) -> Result<Vec<Event>, Box<dyn Error>>;
+
+    fn append_event(&mut self, event: &Event) -> Result<(), Box<dyn Error>>;
+
+    /// Persists the task at `index` within `category`. Backends that cannot
+    /// write a single task fall back to rewriting everything.
+    fn save_task(
+        &mut self,
+        time_period: &TimePeriod,
+        _category: &str,
+        _index: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        self.save(time_period)
+    }
+
+    /// Rewrites the stored data from `time_period` after `load` found it
+    /// unreadable, letting writes through again.
+    fn rebuild(&mut self, time_period: &TimePeriod) -> Result<(), Box<dyn Error>> {
+        self.save(time_period)
+    }
+}
+
+pub struct JsonStorage {
+    path: PathBuf,
+}
+
+impl JsonStorage {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl Storage for JsonStorage {
+    fn load(&self) -> Result<TimePeriod, Box<dyn Error>> {
+        match load_data(&self.path) {
+            Ok(time_period) => Ok(time_period),
+            Err(DataError::Missing(_)) => Ok(TimePeriod {
+                categorization: Categorization::new(),
+            }),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&mut self, time_period: &TimePeriod) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        save_data(&self.path, time_period)?;
+        Ok(())
+    }
+
+    fn rebuild(&mut self, time_period: &TimePeriod) -> Result<(), Box<dyn Error>> {
+        clear_recovery(&self.path)?;
+        self.save(time_period)
+    }
+
+    /// Events live one JSON object per line in `tasks.json.events`. A crash
+    /// while appending can leave the last line without its newline; that
+    /// event never reached the data file, so if it does not parse it is cut
+    /// off, and otherwise its newline is added.
+    fn load_events(&self) -> Result<Vec<Event>, Box<dyn Error>> {
+        let path = sibling_path(&self.path, "events");
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let complete = contents.rfind('\n').map_or(0, |end| end + 1);
+        let mut events = Vec::new();
+        for (number, line) in contents[..complete].lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = serde_json::from_str(line)
+                .map_err(|e| format!("Could not parse {:?} at line {}: {}", path, number + 1, e))?;
+            events.push(event);
+        }
+
+        let torn = &contents[complete..];
+        if !torn.trim().is_empty() {
+            let mut file = OpenOptions::new().write(true).open(&path)?;
+            match serde_json::from_str(torn) {
+                Ok(event) => {
+                    events.push(event);
+                    file.seek(SeekFrom::End(0))?;
+                    file.write_all(b"\n")?;
+                }
+                Err(_) => {
+                    file.set_len(complete as u64)?;
+                    eprintln!(
+                        "Dropped the unfinished last line of {:?}, left by an interrupted change",
+                        path
+                    );
+                }
+            }
+            file.sync_all()?;
+        }
+        Ok(events)
+    }
+
+    fn append_event(&mut self, event: &Event) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(sibling_path(&self.path, "events"))?;
+        let mut line = serde_json::to_string(event)?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
+}
+
+pub struct SqliteStorage {
+    conn: Connection,
+}
+
+/// `SQLITE_MIGRATIONS[i]` upgrades a database whose `user_version` is `i`.
+const SQLITE_MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE IF NOT EXISTS categories (
+        name TEXT PRIMARY KEY
+    );
+    CREATE TABLE IF NOT EXISTS tasks (
+        id INTEGER PRIMARY KEY,
+        category TEXT NOT NULL REFERENCES categories(name) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        status TEXT NOT NULL,
+        paused_secs INTEGER NOT NULL,
+        UNIQUE(category, position)
+    );
+    CREATE TABLE IF NOT EXISTS time_chunks (
+        id INTEGER PRIMARY KEY,
+        task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        start_time TEXT NOT NULL,
+        end_time TEXT,
+        UNIQUE(task_id, position)
+    );
+",
+    "
+    CREATE TABLE events (
+        id INTEGER PRIMARY KEY,
+        timestamp TEXT NOT NULL,
+        user TEXT NOT NULL,
+        action TEXT NOT NULL
+    );
+",
+    "
+    CREATE TABLE pauses (
+        id INTEGER PRIMARY KEY,
+        task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        start_time TEXT NOT NULL,
+        end_time TEXT,
+        UNIQUE(task_id, position)
+    );
+",
+    "
+    ALTER TABLE tasks ADD COLUMN uid TEXT NOT NULL DEFAULT '';
+    ALTER TABLE time_chunks ADD COLUMN uid TEXT NOT NULL DEFAULT '';
+    ALTER TABLE pauses ADD COLUMN uid TEXT NOT NULL DEFAULT '';
+",
+    "
+    ALTER TABLE tasks ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';
+",
+    "
+    ALTER TABLE tasks ADD COLUMN notes TEXT NOT NULL DEFAULT '[]';
+    ALTER TABLE time_chunks ADD COLUMN notes TEXT NOT NULL DEFAULT '[]';
+    ALTER TABLE pauses ADD COLUMN notes TEXT NOT NULL DEFAULT '[]';
+",
+];
+
+impl SqliteStorage {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut conn = Connection::open(path)?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        Self::migrate(&mut conn)?;
+        Ok(Self { conn })
+    }
+
+    fn migrate(conn: &mut Connection) -> Result<(), Box<dyn Error>> {
+        let version: usize =
+            conn.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))? as usize;
+        if version > SQLITE_MIGRATIONS.len() {
+            return Err(format!(
+                "Database uses schema version {}, but this build only understands up to {}",
+                version,
+                SQLITE_MIGRATIONS.len()
+            )
+            .into());
+        }
+        for (step, migration) in SQLITE_MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", (step + 1) as i64)?;
+            tx.commit()?;
+        }
+        Ok(())
+    }
+
+    fn write_task(
+        conn: &Connection,
+        category: &str,
+        position: usize,
+        task: &Task,
+    ) -> rusqlite::Result<()> {
+        conn.execute(
+            "INSERT OR IGNORE INTO categories (name) VALUES (?1)",
+            params![category],
+        )?;
+        let task_id: i64 = conn.query_row(
+            "INSERT INTO tasks (category, position, uid, name, tags, notes, status, paused_secs)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(category, position) DO UPDATE SET
+                uid = excluded.uid,
+                name = excluded.name,
+                tags = excluded.tags,
+                notes = excluded.notes,
+                status = excluded.status,
+                paused_secs = excluded.paused_secs
+             RETURNING id",
+            params![
+                category,
+                position as i64,
+                task.id,
+                task.name,
+                serde_json::to_string(&task.tags).unwrap_or_default(),
+                serde_json::to_string(&task.notes).unwrap_or_default(),
+                status_to_str(&task.status),
+                task.paused_duration.as_secs() as i64
+            ],
+            |row| row.get(0),
+        )?;
+
+        Self::write_intervals(conn, "time_chunks", task_id, &task.time_chunks)?;
+        Self::write_intervals(conn, "pauses", task_id, &task.pauses)?;
+        Ok(())
+    }
+
+    /// Replaces the rows of `table` belonging to `task_id`, leaving unchanged
+    /// rows alone.
+    fn write_intervals(
+        conn: &Connection,
+        table: &str,
+        task_id: i64,
+        intervals: &[TimeChunk],
+    ) -> rusqlite::Result<()> {
+        let mut upsert = conn.prepare_cached(&format!(
+            "INSERT INTO {} (task_id, position, uid, start_time, end_time, notes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(task_id, position) DO UPDATE SET
+                uid = excluded.uid,
+                start_time = excluded.start_time,
+                end_time = excluded.end_time,
+                notes = excluded.notes
+             WHERE uid IS NOT excluded.uid
+                OR start_time IS NOT excluded.start_time
+                OR end_time IS NOT excluded.end_time
+                OR notes IS NOT excluded.notes",
+            table
+        ))?;
+        for (position, interval) in intervals.iter().enumerate() {
+            upsert.execute(params![
+                task_id,
+                position as i64,
+                interval.id,
+                interval.start_time,
+                interval.end_time,
+                serde_json::to_string(&interval.notes).unwrap_or_default()
+            ])?;
+        }
+        conn.execute(
+            &format!(
+                "DELETE FROM {} WHERE task_id = ?1 AND position >= ?2",
+                table
+            ),
+            params![task_id, intervals.len() as i64],
+        )?;
+        Ok(())
+    }
+
+    fn read_intervals(
+        statement: &mut rusqlite::CachedStatement,
+        task_id: i64,
+    ) -> rusqlite::Result<Vec<TimeChunk>> {
+        statement
+            .query_map(params![task_id], |row| {
+                let notes: String = row.get(3)?;
+                Ok(TimeChunk {
+                    id: row.get(0)?,
+                    start_time: row.get(1)?,
+                    end_time: row.get(2)?,
+                    notes: serde_json::from_str(&notes).map_err(|e| {
+                        rusqlite::Error::FromSqlConversionFailure(3, Type::Text, Box::new(e))
+                    })?,
+                })
+            })?
+            .collect()
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load(&self) -> Result<TimePeriod, Box<dyn Error>> {
+        let mut categorization = Categorization::new();
+
+        let mut categories = self.conn.prepare("SELECT name FROM categories")?;
+        for category in categories.query_map([], |row| row.get::<_, String>(0))? {
+            categorization.add_category(category?);
+        }
+
+        let mut tasks = self.conn.prepare(
+            "SELECT id, category, name, status, paused_secs, uid, tags, notes FROM tasks
+             ORDER BY category, position",
+        )?;
+        let mut chunks = self.conn.prepare_cached(
+            "SELECT uid, start_time, end_time, notes FROM time_chunks
+             WHERE task_id = ?1 ORDER BY position",
+        )?;
+        let mut pauses = self.conn.prepare_cached(
+            "SELECT uid, start_time, end_time, notes FROM pauses
+             WHERE task_id = ?1 ORDER BY position",
+        )?;
+        let mut rows = tasks.query([])?;
+        while let Some(row) = rows.next()? {
+            let task_id: i64 = row.get(0)?;
+            let category: String = row.get(1)?;
+            let status: String = row.get(3)?;
+            let tags: String = row.get(6)?;
+            let notes: String = row.get(7)?;
+            let task = Task {
+                id: row.get(5)?,
+                name: row.get(2)?,
+                tags: serde_json::from_str(&tags)?,
+                notes: serde_json::from_str(&notes)?,
+                time_chunks: Self::read_intervals(&mut chunks, task_id)?,
+                pauses: Self::read_intervals(&mut pauses, task_id)?,
+                paused_duration: Duration::from_secs(row.get::<_, i64>(4)? as u64),
+                status: status_from_str(&status)?,
+            };
+            categorization
+                .categories
+                .entry(category)
+                .or_default()
+                .push(task);
+        }
+
+        // Rows written before IDs existed have an empty `uid`.
+        let mut time_period = TimePeriod { categorization };
+        time_period.assign_missing_ids();
+        Ok(time_period)
+    }
+
+    fn save(&mut self, time_period: &TimePeriod) -> Result<(), Box<dyn Error>> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM categories", [])?;
+        for (category, tasks) in &time_period.categorization.categories {
+            tx.execute(
+                "INSERT INTO categories (name) VALUES (?1)",
+                params![category],
+            )?;
+            for (position, task) in tasks.iter().enumerate() {
+                Self::write_task(&tx, category, position, task)?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn load_events(&self) -> Result<Vec<Event>, Box<dyn Error>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT id, timestamp, user, action FROM events ORDER BY id")?;
+        let mut rows = statement.query([])?;
+        let mut events = Vec::new();
+        while let Some(row) = rows.next()? {
+            let action: String = row.get(3)?;
+            events.push(Event {
+                id: row.get::<_, i64>(0)? as u64,
+                timestamp: row.get(1)?,
+                user: row.get(2)?,
+                action: serde_json::from_str(&action)?,
+            });
+        }
+        Ok(events)
+    }
+
+    fn append_event(&mut self, event: &Event) -> Result<(), Box<dyn Error>> {
+        self.conn.execute(
+            "INSERT INTO events (id, timestamp, user, action) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                event.id as i64,
+                event.timestamp,
+                event.user,
+                serde_json::to_string(&event.action)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn save_task(
+        &mut self,
+        time_period: &TimePeriod,
+        category: &str,
+        index: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let task = time_period
+            .categorization
+            .categories
+            .get(category)
+            .and_then(|tasks| tasks.get(index))
+            .ok_or_else(|| format!("No task {} in category {}", index, category))?;
+
+        let tx = self.conn.transaction()?;
+        Self::write_task(&tx, category, index, task)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+fn status_to_str(status: &TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Running => "Running",
+        TaskStatus::Paused => "Paused",
+        TaskStatus::Stopped => "Stopped",
+    }
+}
+
+fn status_from_str(status: &str) -> Result<TaskStatus, Box<dyn Error>> {
+    match status {
+        "Running" => Ok(TaskStatus::Running),
+        "Paused" => Ok(TaskStatus::Paused),
+        "Stopped" => Ok(TaskStatus::Stopped),
+        other => Err(format!("Unknown task status in database: {}", other).into()),
+    }
+}
@@ -1,26 +1,37 @@
 // src/summary.rs
+use crate::categorization::Categorization;
 use crate::data::{Task, TaskStatus};
-use chrono::{Duration, Utc};
-use std::collections::HashMap;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
+/// Prints totals per category, rolled up the category tree, and per tag.
+/// `category_name` limits the summary to that category and everything nested
+/// in it, `depth` limits how many levels below it the tree shows, and only
+/// tasks carrying every tag in `tags` are counted.
 pub fn print_summary_with_duration(
     time_period: &HashMap<String, Vec<Task>>,
     duration: Duration,
     category_name: Option<String>,
+    tags: &BTreeSet<String>,
+    depth: Option<usize>,
 ) {
     let now = Utc::now();
+    let mut tag_totals: BTreeMap<String, Duration> = BTreeMap::new();
+    let mut tree_totals: BTreeMap<Vec<&str>, Duration> = BTreeMap::new();
 
-    for (category, tasks) in time_period {
+    let mut categories: Vec<&String> = time_period.keys().collect();
+    categories.sort();
+    for category in categories {
+        let tasks = &time_period[category];
         if let Some(ref name) = category_name {
-            if name.to_string() != category.to_string() {
+            if !Categorization::is_within(category, name) {
                 continue;
             }
         }
 
-        println!("Category: {:?}", category);
-
         let filtered_tasks: Vec<Task> = tasks
             .iter()
+            .filter(|task| task.all_tags(category).is_superset(tags))
             .filter(|task| {
                 if let Some(chunk) = task.time_chunks.last() {
                     now.signed_duration_since(chunk.start_time) <= duration
@@ -30,19 +41,107 @@ pub fn print_summary_with_duration(
             })
             .cloned()
             .collect();
+        if filtered_tasks.is_empty() && !tags.is_empty() {
+            continue;
+        }
+        println!("Category: {:?}", category);
+
+        // A chunk counts fully towards each of its task's tags.
+        for task in &filtered_tasks {
+            let tracked = tracked_between(task, now - duration, now, now);
+            for tag in task.all_tags(category) {
+                let total = tag_totals.entry(tag).or_insert_with(Duration::zero);
+                *total = *total + tracked;
+            }
+            roll_up(&mut tree_totals, category, tracked);
+        }
 
         let (total_duration, running_task_count, paused_task_count, stopped_task_count) =
-            calculate_summary(&filtered_tasks);
+            calculate_summary(&filtered_tasks, now - duration, now);
 
         println!("Total duration: {}", format_duration(total_duration));
         println!("Running tasks: {}", running_task_count);
         println!("Paused tasks: {}", paused_task_count);
         println!("Stopped tasks: {}", stopped_task_count);
-        println!("");
+        println!();
+    }
+
+    let top = category_name
+        .as_deref()
+        .map_or(1, |name| Categorization::category_path(name).len().max(1));
+    let bottom = depth.map_or(usize::MAX, |depth| top.saturating_add(depth));
+    if !tree_totals.is_empty() {
+        println!("Category tree:");
+        for (path, total) in &tree_totals {
+            if path.len() < top || path.len() > bottom {
+                continue;
+            }
+            let label = if path.len() == top {
+                let name = path.join("/");
+                if name == "Uncategorized" {
+                    name
+                } else {
+                    format!("#{}", name)
+                }
+            } else {
+                path[path.len() - 1].to_string()
+            };
+            println!(
+                "{}{}: {}",
+                "  ".repeat(path.len() - top),
+                label,
+                format_duration(*total)
+            );
+        }
+        println!();
+    }
+
+    if !tag_totals.is_empty() {
+        println!("Tags:");
+        for (tag, total) in &tag_totals {
+            println!("#{}: {}", tag, format_duration(*total));
+        }
+    }
+}
+
+/// Time `task` tracked between `from` and `to`, counting a running chunk up
+/// to `now`.
+pub fn tracked_between(
+    task: &Task,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Duration {
+    task.time_chunks
+        .iter()
+        .fold(Duration::zero(), |acc, chunk| {
+            acc + chunk.duration_within(from, to, now)
+        })
+}
+
+/// Adds `tracked` to `category` and to every category it is nested in, keyed
+/// by category path, so each level counts everything below it.
+pub fn roll_up<'a>(
+    totals: &mut BTreeMap<Vec<&'a str>, Duration>,
+    category: &'a str,
+    tracked: Duration,
+) {
+    let path = Categorization::category_path(category);
+    for level in 1..=path.len() {
+        let total = totals
+            .entry(path[..level].to_vec())
+            .or_insert_with(Duration::zero);
+        *total = *total + tracked;
     }
 }
 
-fn calculate_summary(tasks: &[Task]) -> (Duration, usize, usize, usize) {
+/// Time tracked by `tasks` between `from` and `now`, and how many of them are
+/// running, paused and stopped.
+fn calculate_summary(
+    tasks: &[Task],
+    from: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> (Duration, usize, usize, usize) {
     let mut total_duration = Duration::zero();
     let mut running_task_count = 0;
     let mut paused_task_count = 0;
@@ -60,8 +159,7 @@ fn calculate_summary(tasks: &[Task]) -> (Duration, usize, usize, usize) {
                 stopped_task_count += 1;
             }
         }
-        let task_duration = task.chrono_duration();
-        total_duration = total_duration + task_duration;
+        total_duration = total_duration + tracked_between(task, from, now, now);
     }
 
     (
@@ -72,7 +170,7 @@ fn calculate_summary(tasks: &[Task]) -> (Duration, usize, usize, usize) {
     )
 }
 
-fn format_duration(duration: Duration) -> String {
+pub fn format_duration(duration: Duration) -> String {
     let hours = duration.num_hours();
     let minutes = duration.num_minutes() % 60;
     let seconds = duration.num_seconds() % 60;
@@ -0,0 +1,937 @@
+// src/tui.rs
+use crate::categorization::Categorization;
+use crate::config::AppConfig;
+use crate::data::{self, Task, TaskStatus, TimeChunk, TimePeriod};
+use crate::events::{Action, Journal};
+use crate::storage;
+use crate::summary::{format_duration, roll_up, tracked_between};
+use chrono::{DateTime, Local, Utc};
+use notify::{EventKind, RecursiveMode, Watcher};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::mpsc::Receiver;
+
+/// How often the running timer ticks.
+const TICK: std::time::Duration = std::time::Duration::from_millis(250);
+
+const HELP: &str =
+    "↑/↓ select  s start  n new  p pause/resume  x stop  e edit times  r rename  u undo  q quit";
+
+type Changes = Receiver<notify::Result<notify::Event>>;
+
+/// A line being typed at the bottom of the screen.
+enum Prompt {
+    /// Description of a task to start, `#category` and tags included.
+    Start(String),
+    /// New name for the selected task.
+    Rename(String),
+    /// New times for the selected task's last chunk, as `start - end`.
+    Edit(String),
+}
+
+impl Prompt {
+    fn text(&mut self) -> &mut String {
+        match self {
+            Prompt::Start(text) | Prompt::Rename(text) | Prompt::Edit(text) => text,
+        }
+    }
+}
+
+struct Dashboard {
+    /// Every task with its category, most used first.
+    tasks: Vec<(String, Task)>,
+    selected: ListState,
+    prompt: Option<Prompt>,
+    message: String,
+}
+
+/// Shows the dashboard until the user quits. The storage lock is only held
+/// while reading or writing, so other commands keep working meanwhile and
+/// the dashboard picks up their changes as soon as they hit the disk.
+pub fn run(config: &AppConfig) -> Result<(), Box<dyn Error>> {
+    if !std::io::stdout().is_terminal() {
+        return Err("tt tui needs a terminal".into());
+    }
+    let mut dashboard = Dashboard::new(load(config)?);
+
+    let path = config.storage_path();
+    let (sender, changes) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(
+        path.parent().unwrap_or(Path::new(".")),
+        RecursiveMode::NonRecursive,
+    )?;
+
+    // Errors from changes end up in the status line; anything that stops the
+    // dashboard, even a panic, leaves the terminal restored.
+    let mut terminal = ratatui::try_init()?;
+    let result = dashboard.run(&mut terminal, config, &changes);
+    ratatui::restore();
+    result
+}
+
+/// Reads the data, holding the lock only while doing so.
+fn load(config: &AppConfig) -> Result<TimePeriod, Box<dyn Error>> {
+    change(config, |_, _| Ok(String::new())).map(|(time_period, _)| time_period)
+}
+
+/// Reads the data under the lock and lets `decide` record changes to it, so
+/// nothing is ever recorded against a stale copy. Returns the data as it is
+/// afterwards and what `decide` has to say about it.
+fn change(
+    config: &AppConfig,
+    decide: impl FnOnce(&mut TimePeriod, &mut Journal) -> Result<String, String>,
+) -> Result<(TimePeriod, String), Box<dyn Error>> {
+    let _lock = storage::lock(&config.storage_path())?;
+    let mut storage = storage::open(config)?;
+    let (mut journal, mut time_period) = Journal::open(storage.as_mut())?;
+    let message = decide(&mut time_period, &mut journal)?;
+    Ok((time_period, message))
+}
+
+/// Whether a file system event changed the data file or a file kept next to
+/// it, such as the event log.
+fn touches(change: &notify::Result<notify::Event>, path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    match change {
+        Ok(change) => {
+            !matches!(change.kind, EventKind::Access(_))
+                && change.paths.iter().any(|changed| {
+                    changed
+                        .file_name()
+                        .is_some_and(|changed| changed.to_string_lossy().starts_with(&*name))
+                })
+        }
+        Err(_) => true,
+    }
+}
+
+/// The task with `id`, with its category.
+fn by_id<'a>(time_period: &'a TimePeriod, id: &str) -> Result<(&'a String, &'a Task), String> {
+    time_period
+        .categorization
+        .categories
+        .iter()
+        .flat_map(|(category, tasks)| tasks.iter().map(move |task| (category, task)))
+        .find(|(_, task)| task.id == id)
+        .ok_or_else(|| String::from("That task no longer exists"))
+}
+
+/// The `Start` for `description`, stopping or pausing what runs according to
+/// the running policy.
+fn start(time_period: &TimePeriod, config: &AppConfig, description: String) -> Action {
+    let (_, _, tags) = Categorization::parse_description(&description);
+    Action::Start {
+        displaced: crate::displaced_tasks(time_period, config.running_policy, None),
+        description,
+        at: Utc::now(),
+        task_id: data::new_id(),
+        chunk_id: data::new_id(),
+        tags: Some(tags),
+    }
+}
+
+fn status_mark(status: &TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Running => "▶",
+        TaskStatus::Paused => "⏸",
+        TaskStatus::Stopped => " ",
+    }
+}
+
+/// The times of `chunk` as offered for editing: `09:00 - 10:30`, with the
+/// date of the end if it is on another day, or just the start while the
+/// chunk runs.
+fn chunk_times(chunk: &TimeChunk) -> String {
+    let start = chunk.start_time.with_timezone(&Local);
+    match chunk.end_time {
+        Some(end) => {
+            let end = end.with_timezone(&Local);
+            let format = if end.date_naive() == start.date_naive() {
+                "%H:%M"
+            } else {
+                "%Y-%m-%d %H:%M"
+            };
+            format!("{} - {}", start.format("%H:%M"), end.format(format))
+        }
+        None => start.format("%H:%M").to_string(),
+    }
+}
+
+/// Splits `start - end` as typed into the start and, if given, the end.
+/// Between two times of day the spaces can be left out (`09:00-10:30`).
+fn split_times(times: &str) -> (&str, Option<&str>) {
+    let (start, end) = times
+        .split_once(" - ")
+        .or_else(|| {
+            times
+                .split_once('-')
+                .filter(|(start, _)| crate::utils::parse_time_of_day(start.trim()).is_some())
+        })
+        .unwrap_or((times, ""));
+    let end = end.trim();
+    (start.trim(), (!end.is_empty()).then_some(end))
+}
+
+fn start_of_today() -> DateTime<Utc> {
+    let midnight = Local::now().date_naive().and_hms_opt(0, 0, 0).unwrap();
+    crate::utils::local_to_utc(midnight).unwrap_or_else(|_| Utc::now())
+}
+
+impl Dashboard {
+    fn new(time_period: TimePeriod) -> Self {
+        let mut dashboard = Dashboard {
+            tasks: Vec::new(),
+            selected: ListState::default(),
+            prompt: None,
+            message: String::new(),
+        };
+        dashboard.show(time_period);
+        dashboard
+    }
+
+    /// Switches to freshly read data, keeping the same task selected.
+    fn show(&mut self, time_period: TimePeriod) {
+        let selected = self.selected_task().map(|(_, task)| task.id.clone());
+        self.tasks = crate::recent_tasks(&time_period, |_| true)
+            .into_iter()
+            .map(|(category, task)| (category.clone(), task.clone()))
+            .collect();
+        let index = selected
+            .and_then(|id| self.tasks.iter().position(|(_, task)| task.id == id))
+            .unwrap_or(0);
+        self.selected
+            .select((!self.tasks.is_empty()).then_some(index));
+    }
+
+    fn selected_task(&self) -> Option<&(String, Task)> {
+        self.tasks.get(self.selected.selected()?)
+    }
+
+    fn run(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        config: &AppConfig,
+        changes: &Changes,
+    ) -> Result<(), Box<dyn Error>> {
+        let path = config.storage_path();
+        let mut stale = false;
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if event::poll(TICK)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && !self.handle_key(key.code, config) {
+                        return Ok(());
+                    }
+                }
+            }
+            stale |= changes.try_iter().any(|change| touches(&change, &path));
+            // Another command may be holding the lock; try again next tick.
+            if stale {
+                match load(config) {
+                    Ok(time_period) => {
+                        self.show(time_period);
+                        stale = false;
+                    }
+                    Err(e) => self.message = e.to_string(),
+                }
+            }
+        }
+    }
+
+    /// Handles one key press, returning `false` to quit.
+    fn handle_key(&mut self, key: KeyCode, config: &AppConfig) -> bool {
+        if let Some(prompt) = &mut self.prompt {
+            match key {
+                KeyCode::Esc => self.prompt = None,
+                KeyCode::Enter => {
+                    if let Some(prompt) = self.prompt.take() {
+                        self.submit(prompt, config);
+                    }
+                }
+                KeyCode::Backspace => {
+                    prompt.text().pop();
+                }
+                KeyCode::Char(c) => prompt.text().push(c),
+                _ => {}
+            }
+            return true;
+        }
+
+        self.message.clear();
+        let selected = self.selected.selected().unwrap_or(0);
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Down | KeyCode::Char('j') if selected + 1 < self.tasks.len() => {
+                self.selected.select(Some(selected + 1))
+            }
+            KeyCode::Up | KeyCode::Char('k') if selected > 0 => {
+                self.selected.select(Some(selected - 1))
+            }
+            KeyCode::Enter | KeyCode::Char('s') => {
+                self.act_on_selected(config, |time_period, config, category, task| {
+                    if task.status == TaskStatus::Running {
+                        return Err(format!("{:?} is already running", task.name));
+                    }
+                    let description = match category {
+                        crate::UNCATEGORIZED => task.name.clone(),
+                        category => format!("{} {}", task.name, category),
+                    };
+                    Ok((
+                        start(time_period, config, description),
+                        format!("Started {:?}", task.name),
+                    ))
+                })
+            }
+            KeyCode::Char('p') => {
+                self.act_on_selected(config, |time_period, config, category, task| {
+                    let (category, name) = (category.to_string(), task.name.clone());
+                    match task.status {
+                        TaskStatus::Running => Ok((
+                            Action::Pause {
+                                category,
+                                task: name.clone(),
+                                at: Utc::now(),
+                            },
+                            format!("Paused {:?}", name),
+                        )),
+                        TaskStatus::Paused => Ok((
+                            Action::Resume {
+                                displaced: crate::displaced_tasks(
+                                    time_period,
+                                    config.running_policy,
+                                    None,
+                                ),
+                                category,
+                                task: name.clone(),
+                                at: Utc::now(),
+                                chunk_id: data::new_id(),
+                            },
+                            format!("Resumed {:?}", name),
+                        )),
+                        TaskStatus::Stopped => Err(format!("{:?} is not running", name)),
+                    }
+                })
+            }
+            KeyCode::Char('x') => self.act_on_selected(config, |_, _, category, task| {
+                if task.status == TaskStatus::Stopped {
+                    return Err(format!("{:?} is not running", task.name));
+                }
+                Ok((
+                    Action::Stop {
+                        category: category.to_string(),
+                        task: task.name.clone(),
+                        at: Utc::now(),
+                    },
+                    format!("Stopped {:?}", task.name),
+                ))
+            }),
+            KeyCode::Char('n') => self.prompt = Some(Prompt::Start(String::new())),
+            KeyCode::Char('e') => {
+                if let Some(chunk) = self
+                    .selected_task()
+                    .and_then(|(_, task)| task.time_chunks.last())
+                {
+                    self.prompt = Some(Prompt::Edit(chunk_times(chunk)));
+                }
+            }
+            KeyCode::Char('r') => {
+                if let Some((_, task)) = self.selected_task() {
+                    self.prompt = Some(Prompt::Rename(task.name.clone()));
+                }
+            }
+            KeyCode::Char('u') => self.update(config, |time_period, journal| {
+                match journal.undo(time_period).map_err(|e| e.to_string())? {
+                    Some(event) => Ok(format!("Undid {}", event.action)),
+                    None => Ok(String::from("Nothing to undo")),
+                }
+            }),
+            _ => {}
+        }
+        true
+    }
+
+    fn submit(&mut self, prompt: Prompt, config: &AppConfig) {
+        match prompt {
+            Prompt::Start(description) => {
+                let (category, name, _) = Categorization::parse_description(&description);
+                self.update(config, |time_period, journal| {
+                    if name.is_empty() {
+                        return Err(String::from("Give the task a name"));
+                    }
+                    let running = crate::find_task(time_period, &category, &name)
+                        .is_some_and(|task| task.status == TaskStatus::Running);
+                    if running {
+                        return Err(format!("{:?} is already running", name));
+                    }
+                    let action = start(time_period, config, description.clone());
+                    crate::record(time_period, journal, action)?;
+                    Ok(format!("Started {:?}", name))
+                });
+                // Whatever is typed in next most likely concerns the new task.
+                let started = self
+                    .tasks
+                    .iter()
+                    .position(|(c, task)| *c == category && task.name == name);
+                if started.is_some() {
+                    self.selected.select(started);
+                }
+            }
+            Prompt::Rename(new_name) => {
+                let Some((_, task)) = self.selected_task() else {
+                    return;
+                };
+                let id = task.id.clone();
+                self.update(config, |time_period, journal| {
+                    let (category, task) = by_id(time_period, &id)?;
+                    let (category, name) = (category.clone(), task.name.clone());
+                    let edited = crate::renamed(time_period, &category, &name, new_name.trim())?;
+                    crate::record_changes(time_period, journal, &edited)?;
+                    Ok(format!("Renamed {:?} to {:?}", name, new_name.trim()))
+                })
+            }
+            Prompt::Edit(times) => {
+                let Some((_, task)) = self.selected_task() else {
+                    return;
+                };
+                let id = task.id.clone();
+                self.update(config, |time_period, journal| {
+                    let (category, task) = by_id(time_period, &id)?;
+                    let (category, name) = (category.clone(), task.name.clone());
+                    let index = task
+                        .time_chunks
+                        .len()
+                        .checked_sub(1)
+                        .ok_or_else(|| format!("{:?} has no chunks", name))?;
+                    // Only what was changed is parsed, so leaving a time as
+                    // offered keeps its seconds.
+                    let offered = chunk_times(&task.time_chunks[index]);
+                    let (old_start, old_end) = split_times(&offered);
+                    let (start, end) = split_times(&times);
+                    let edit = crate::ChunkEdit::Times {
+                        start: (start != old_start).then(|| start.to_string()),
+                        end: end.filter(|&end| Some(end) != old_end).map(String::from),
+                    };
+                    let (edited, message) =
+                        crate::chunk_edited(time_period, &category, &name, index, edit)?;
+                    crate::record_changes(time_period, journal, &edited)?;
+                    Ok(message)
+                })
+            }
+        }
+    }
+
+    /// Records the action `decide` picks for the selected task, as it is on
+    /// disk right now.
+    fn act_on_selected(
+        &mut self,
+        config: &AppConfig,
+        decide: impl FnOnce(&TimePeriod, &AppConfig, &str, &Task) -> Result<(Action, String), String>,
+    ) {
+        let Some((_, task)) = self.selected_task() else {
+            return;
+        };
+        let id = task.id.clone();
+        self.update(config, |time_period, journal| {
+            let (category, task) = by_id(time_period, &id)?;
+            let (action, message) = decide(time_period, config, category, task)?;
+            crate::record(time_period, journal, action)?;
+            Ok(message)
+        })
+    }
+
+    fn update(
+        &mut self,
+        config: &AppConfig,
+        decide: impl FnOnce(&mut TimePeriod, &mut Journal) -> Result<String, String>,
+    ) {
+        match change(config, decide) {
+            Ok((time_period, message)) => {
+                self.show(time_period);
+                self.message = message;
+            }
+            Err(e) => self.message = e.to_string(),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let now = Utc::now();
+        let active: Vec<&(String, Task)> = self
+            .tasks
+            .iter()
+            .filter(|(_, task)| task.status != TaskStatus::Stopped)
+            .collect();
+        let [current, middle, footer] = Layout::vertical([
+            Constraint::Length(active.len().max(1) as u16 + 2),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [tasks, today] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(middle);
+        let [timeline, totals] =
+            Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(today);
+
+        let lines: Vec<String> = if active.is_empty() {
+            vec![String::from("Nothing is running")]
+        } else {
+            active
+                .iter()
+                .map(|(category, task)| match task.status {
+                    TaskStatus::Paused => format!(
+                        "{} {} ({}), paused for {}",
+                        status_mark(&task.status),
+                        task.name,
+                        category,
+                        format_duration(task.paused(now))
+                    ),
+                    _ => format!(
+                        "{} {} ({}): {}, {} in total",
+                        status_mark(&task.status),
+                        task.name,
+                        category,
+                        format_duration(
+                            task.time_chunks
+                                .last()
+                                .map_or_else(chrono::Duration::zero, |chunk| chunk.duration(now))
+                        ),
+                        format_duration(task.tracked(now))
+                    ),
+                })
+                .collect()
+        };
+        frame.render_widget(
+            Paragraph::new(lines.join("\n"))
+                .block(Block::default().borders(Borders::ALL).title("Now")),
+            current,
+        );
+
+        self.draw_tasks(frame, tasks);
+        self.draw_today(frame, timeline, totals, now);
+
+        let footer_text = match &self.prompt {
+            Some(Prompt::Start(text)) => format!("Start: {}▏", text),
+            Some(Prompt::Rename(text)) => format!("Rename to: {}▏", text),
+            Some(Prompt::Edit(text)) => format!("Last chunk (start - end): {}▏", text),
+            None if !self.message.is_empty() => self.message.clone(),
+            None => String::from(HELP),
+        };
+        frame.render_widget(Paragraph::new(footer_text), footer);
+    }
+
+    fn draw_tasks(&mut self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .tasks
+            .iter()
+            .map(|(category, task)| {
+                ListItem::new(format!(
+                    "{} {} ({})",
+                    status_mark(&task.status),
+                    task.name,
+                    category
+                ))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Tasks"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.selected);
+    }
+
+    /// Today's chunks in order, and the time they add up to per category.
+    fn draw_today(&self, frame: &mut Frame, timeline: Rect, totals: Rect, now: DateTime<Utc>) {
+        let today = start_of_today();
+        let mut chunks: Vec<(DateTime<Utc>, String)> = Vec::new();
+        let mut tree: BTreeMap<Vec<&str>, chrono::Duration> = BTreeMap::new();
+        let mut total = chrono::Duration::zero();
+        for (category, task) in &self.tasks {
+            for chunk in &task.time_chunks {
+                if !chunk.overlaps(today, now, now) {
+                    continue;
+                }
+                let end = match chunk.end_time {
+                    Some(end) => end.with_timezone(&Local).format("%H:%M").to_string(),
+                    None => String::from("now"),
+                };
+                chunks.push((
+                    chunk.start_time,
+                    format!(
+                        "{}–{} {} ({})",
+                        chunk.start_time.with_timezone(&Local).format("%H:%M"),
+                        end,
+                        task.name,
+                        category
+                    ),
+                ));
+            }
+            let tracked = tracked_between(task, today, now, now);
+            if tracked > chrono::Duration::zero() {
+                roll_up(&mut tree, category, tracked);
+                total = total + tracked;
+            }
+        }
+        chunks.sort();
+
+        let timeline_items: Vec<ListItem> = chunks
+            .into_iter()
+            .map(|(_, line)| ListItem::new(line))
+            .collect();
+        frame.render_widget(
+            List::new(timeline_items).block(Block::default().borders(Borders::ALL).title("Today")),
+            timeline,
+        );
+
+        let total_items: Vec<ListItem> = tree
+            .iter()
+            .map(|(path, tracked)| {
+                let label = match path.len() {
+                    1 if path[0] == crate::UNCATEGORIZED => path[0].to_string(),
+                    1 => format!("#{}", path[0]),
+                    _ => path[path.len() - 1].to_string(),
+                };
+                ListItem::new(format!(
+                    "{}{}: {}",
+                    "  ".repeat(path.len() - 1),
+                    label,
+                    format_duration(*tracked)
+                ))
+            })
+            .collect();
+        frame.render_widget(
+            List::new(total_items).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Categories today ({})", format_duration(total))),
+            ),
+            totals,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::local_to_utc;
+    use chrono::{NaiveDate, NaiveTime};
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use tempfile::TempDir;
+
+    /// A configuration keeping everything in a throwaway directory.
+    fn config(dir: &TempDir) -> AppConfig {
+        AppConfig {
+            storage_location: Some(dir.path().join("tasks.json")),
+            ..AppConfig::default()
+        }
+    }
+
+    /// Local time `hour:minute` on 2024-03-05.
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        local_to_utc(day.and_time(NaiveTime::from_hms_opt(hour, minute, 0).unwrap())).unwrap()
+    }
+
+    /// Data holding a stopped "meeting" from 10:00 to 11:00 on 2024-03-05,
+    /// under `#acme`.
+    fn with_meeting(config: &AppConfig) {
+        let mut categorization = Categorization::new();
+        categorization.categories.insert(
+            String::from("#acme"),
+            vec![Task::from_chunks(
+                "meeting",
+                vec![TimeChunk::new(at(10, 0), Some(at(11, 0)))],
+            )],
+        );
+        data::save_data(&config.storage_path(), &TimePeriod { categorization }).unwrap();
+    }
+
+    fn start_task(config: &AppConfig, description: &str) {
+        change(config, |time_period, journal| {
+            let action = start(time_period, config, description.to_string());
+            crate::record(time_period, journal, action)?;
+            Ok(String::new())
+        })
+        .unwrap();
+    }
+
+    fn stored(config: &AppConfig, category: &str, name: &str) -> Task {
+        crate::find_task(&load(config).unwrap(), category, name)
+            .unwrap()
+            .clone()
+    }
+
+    fn press(dashboard: &mut Dashboard, config: &AppConfig, keys: &str) {
+        for c in keys.chars() {
+            assert!(dashboard.handle_key(KeyCode::Char(c), config));
+        }
+    }
+
+    /// The dashboard as drawn on a 100×20 terminal, one line per row.
+    fn screen(dashboard: &mut Dashboard) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|frame| dashboard.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn draws_the_running_task_today_and_every_task() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir);
+        with_meeting(&config);
+        start_task(&config, "write docs #web");
+        let mut dashboard = Dashboard::new(load(&config).unwrap());
+
+        let screen = screen(&mut dashboard);
+
+        assert!(
+            screen.contains("▶ write docs (#web): 00h 00m 00s, 00h 00m 00s in total"),
+            "{}",
+            screen
+        );
+        assert!(screen.contains("▶ write docs (#web)  "), "{}", screen);
+        assert!(screen.contains("  meeting (#acme)"), "{}", screen);
+        assert!(screen.contains("–now write docs (#web)"), "{}", screen);
+        assert!(screen.contains("#web: 00h 00m 00s"), "{}", screen);
+        assert!(screen.contains("e edit times  r rename"), "{}", screen);
+    }
+
+    #[test]
+    fn draws_an_idle_dashboard() {
+        let mut dashboard = Dashboard::new(TimePeriod {
+            categorization: Categorization::new(),
+        });
+
+        let screen = screen(&mut dashboard);
+
+        assert!(screen.contains("Nothing is running"), "{}", screen);
+        assert!(
+            screen.contains("Categories today (00h 00m 00s)"),
+            "{}",
+            screen
+        );
+    }
+
+    #[test]
+    fn keys_move_the_selection_and_q_quits() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir);
+        with_meeting(&config);
+        start_task(&config, "write docs");
+        let mut dashboard = Dashboard::new(load(&config).unwrap());
+        assert_eq!(dashboard.selected_task().unwrap().1.name, "write docs");
+
+        assert!(dashboard.handle_key(KeyCode::Down, &config));
+        assert_eq!(dashboard.selected_task().unwrap().1.name, "meeting");
+        assert!(dashboard.handle_key(KeyCode::Down, &config));
+        assert_eq!(dashboard.selected_task().unwrap().1.name, "meeting");
+        press(&mut dashboard, &config, "k");
+        assert_eq!(dashboard.selected_task().unwrap().1.name, "write docs");
+
+        assert!(!dashboard.handle_key(KeyCode::Char('q'), &config));
+    }
+
+    #[test]
+    fn keys_start_pause_and_stop_the_selected_task() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir);
+        with_meeting(&config);
+        let mut dashboard = Dashboard::new(load(&config).unwrap());
+
+        press(&mut dashboard, &config, "s");
+        assert_eq!(dashboard.message, "Started \"meeting\"");
+        assert_eq!(
+            stored(&config, "#acme", "meeting").status,
+            TaskStatus::Running
+        );
+
+        press(&mut dashboard, &config, "p");
+        assert_eq!(
+            stored(&config, "#acme", "meeting").status,
+            TaskStatus::Paused
+        );
+        press(&mut dashboard, &config, "p");
+        assert_eq!(
+            stored(&config, "#acme", "meeting").status,
+            TaskStatus::Running
+        );
+
+        press(&mut dashboard, &config, "x");
+        assert_eq!(
+            stored(&config, "#acme", "meeting").status,
+            TaskStatus::Stopped
+        );
+        press(&mut dashboard, &config, "x");
+        assert_eq!(dashboard.message, "\"meeting\" is not running");
+
+        press(&mut dashboard, &config, "u");
+        assert_eq!(
+            stored(&config, "#acme", "meeting").status,
+            TaskStatus::Running
+        );
+    }
+
+    #[test]
+    fn new_task_prompt_starts_and_selects_it() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir);
+        with_meeting(&config);
+        let mut dashboard = Dashboard::new(load(&config).unwrap());
+
+        press(&mut dashboard, &config, "nreview #webb");
+        assert!(dashboard.handle_key(KeyCode::Backspace, &config));
+        assert!(screen(&mut dashboard).contains("Start: review #web▏"));
+        assert!(dashboard.handle_key(KeyCode::Enter, &config));
+
+        assert_eq!(
+            stored(&config, "#web", "review").status,
+            TaskStatus::Running
+        );
+        assert_eq!(dashboard.selected_task().unwrap().1.name, "review");
+        assert!(dashboard.prompt.is_none());
+    }
+
+    #[test]
+    fn escape_leaves_a_prompt_without_changing_anything() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir);
+        with_meeting(&config);
+        let mut dashboard = Dashboard::new(load(&config).unwrap());
+
+        press(&mut dashboard, &config, "rstandup");
+        assert!(dashboard.handle_key(KeyCode::Esc, &config));
+
+        assert!(dashboard.prompt.is_none());
+        assert_eq!(stored(&config, "#acme", "meeting").name, "meeting");
+    }
+
+    #[test]
+    fn r_renames_the_selected_task() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir);
+        with_meeting(&config);
+        let mut dashboard = Dashboard::new(load(&config).unwrap());
+
+        press(&mut dashboard, &config, "r");
+        assert!(screen(&mut dashboard).contains("Rename to: meeting▏"));
+        press(&mut dashboard, &config, " notes");
+        assert!(dashboard.handle_key(KeyCode::Enter, &config));
+
+        assert_eq!(
+            dashboard.message,
+            "Renamed \"meeting\" to \"meeting notes\""
+        );
+        assert!(crate::find_task(&load(&config).unwrap(), "#acme", "meeting").is_none());
+        assert_eq!(
+            stored(&config, "#acme", "meeting notes").time_chunks.len(),
+            1
+        );
+    }
+
+    #[test]
+    fn e_edits_the_times_of_the_last_chunk() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir);
+        with_meeting(&config);
+        let mut dashboard = Dashboard::new(load(&config).unwrap());
+
+        press(&mut dashboard, &config, "e");
+        assert!(screen(&mut dashboard).contains("Last chunk (start - end): 10:00 - 11:00▏"));
+        for _ in "10:00 - 11:00".chars() {
+            assert!(dashboard.handle_key(KeyCode::Backspace, &config));
+        }
+        press(&mut dashboard, &config, "09:30-11:00");
+        assert!(dashboard.handle_key(KeyCode::Enter, &config));
+
+        assert_eq!(dashboard.message, "Updated chunk 1 of \"meeting\"");
+        let chunk = &stored(&config, "#acme", "meeting").time_chunks[0];
+        assert_eq!(chunk.start_time, at(9, 30));
+        assert_eq!(chunk.end_time, Some(at(11, 0)));
+    }
+
+    #[test]
+    fn e_keeps_untouched_times_exact_and_refuses_bad_ones() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir);
+        start_task(&config, "write docs");
+        let before = stored(&config, "Uncategorized", "write docs");
+        let mut dashboard = Dashboard::new(load(&config).unwrap());
+
+        press(&mut dashboard, &config, "e");
+        assert!(dashboard.handle_key(KeyCode::Enter, &config));
+        assert_eq!(stored(&config, "Uncategorized", "write docs"), before);
+
+        press(&mut dashboard, &config, "e - 23:59");
+        assert!(dashboard.handle_key(KeyCode::Enter, &config));
+        assert_eq!(
+            dashboard.message,
+            "That chunk is still running; use `tt stop` to end it"
+        );
+        assert_eq!(stored(&config, "Uncategorized", "write docs"), before);
+    }
+
+    #[test]
+    fn failed_save_shows_in_the_status_line() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir);
+        with_meeting(&config);
+        let mut dashboard = Dashboard::new(load(&config).unwrap());
+        // Saving writes the temp file first; make that impossible.
+        std::fs::create_dir(dir.path().join("tasks.json.tmp")).unwrap();
+
+        press(&mut dashboard, &config, "r");
+        press(&mut dashboard, &config, " notes");
+        assert!(dashboard.handle_key(KeyCode::Enter, &config));
+        assert!(
+            dashboard.message.contains("data file"),
+            "{}",
+            dashboard.message
+        );
+        let message = dashboard.message.clone();
+        assert!(screen(&mut dashboard).contains(&message));
+
+        press(&mut dashboard, &config, "e");
+        assert!(dashboard.handle_key(KeyCode::Backspace, &config));
+        press(&mut dashboard, &config, "5");
+        assert!(dashboard.handle_key(KeyCode::Enter, &config));
+        assert!(
+            dashboard.message.contains("data file"),
+            "{}",
+            dashboard.message
+        );
+
+        press(&mut dashboard, &config, "s");
+        assert!(
+            dashboard.message.contains("data file"),
+            "{}",
+            dashboard.message
+        );
+    }
+
+    #[test]
+    fn split_times_accepts_both_spellings_and_dates() {
+        assert_eq!(split_times("09:00 - 10:30"), ("09:00", Some("10:30")));
+        assert_eq!(split_times(" 09:00-10:30 "), ("09:00", Some("10:30")));
+        assert_eq!(split_times("09:00"), ("09:00", None));
+        assert_eq!(
+            split_times("23:00 - 2024-03-06 01:00"),
+            ("23:00", Some("2024-03-06 01:00"))
+        );
+        assert_eq!(split_times("2024-03-05 09:00"), ("2024-03-05 09:00", None));
+    }
+}
@@ -0,0 +1,243 @@
+// src/utils.rs
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+    Weekday,
+};
+
+/// Asks a yes/no question on the terminal, treating any prompt failure
+/// (e.g. no TTY) as "no".
+pub fn confirm(message: &str) -> bool {
+    let question = requestty::Question::confirm("confirm")
+        .message(message)
+        .default(false)
+        .build();
+    requestty::prompt_one(question)
+        .ok()
+        .and_then(|answer| answer.as_bool())
+        .unwrap_or(false)
+}
+
+/// Offers `choices` on the terminal and returns the index picked, or `None`
+/// if the prompt could not be shown or was aborted.
+pub fn choose(message: &str, choices: &[&str]) -> Option<usize> {
+    let question = requestty::Question::select("choice")
+        .message(message)
+        .choices(choices.iter().copied())
+        .build();
+    requestty::prompt_one(question)
+        .ok()
+        .and_then(|answer| answer.as_list_item().map(|item| item.index))
+}
+
+/// What was picked with `pick`.
+pub enum Picked {
+    /// Index into the offered items.
+    Item(usize),
+    /// Text typed to create something new.
+    New(String),
+}
+
+/// Lets the user narrow `items` down by typing part of one (Tab completes),
+/// then choose among the matches. With `allow_new`, the typed text can also
+/// be taken as a new entry. Returns `None` if the prompt could not be shown,
+/// was aborted, or nothing matched.
+pub fn pick(message: &str, items: &[String], allow_new: bool) -> Option<Picked> {
+    let question = requestty::Question::input("filter")
+        .message(format!("{} (type to filter, Tab to complete)", message))
+        .auto_complete(|typed, _| {
+            let found = matching(items, &typed);
+            if found.is_empty() {
+                std::iter::once(typed).collect()
+            } else {
+                found
+                    .into_iter()
+                    .map(|index| items[index].clone())
+                    .collect()
+            }
+        })
+        .build();
+    let filter = requestty::prompt_one(question).ok()?;
+    let filter = filter.as_string()?.trim().to_string();
+    if let Some(index) = items.iter().position(|item| *item == filter) {
+        return Some(Picked::Item(index));
+    }
+
+    let found = matching(items, &filter);
+    let mut choices: Vec<&str> = found.iter().map(|&index| items[index].as_str()).collect();
+    let new = format!("New task {:?}", filter);
+    if allow_new && !filter.is_empty() {
+        choices.push(&new);
+    }
+    match choices.len() {
+        0 => None,
+        1 if found.len() == 1 => Some(Picked::Item(found[0])),
+        _ => match choose(message, &choices)? {
+            choice if choice < found.len() => Some(Picked::Item(found[choice])),
+            _ => Some(Picked::New(filter)),
+        },
+    }
+}
+
+/// Indices of the `items` containing every word of `filter`, ignoring case.
+fn matching(items: &[String], filter: &str) -> Vec<usize> {
+    let words: Vec<String> = filter.split_whitespace().map(str::to_lowercase).collect();
+    items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| {
+            let item = item.to_lowercase();
+            words.iter().all(|word| item.contains(word))
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Parses a length of time such as `1h25m`, `90m`, `45s` or `1:25`.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim().to_lowercase();
+    let invalid = || format!("Could not understand the duration {:?}", input);
+    if let Some((hours, minutes)) = input.split_once(':') {
+        let hours: i64 = hours.parse().map_err(|_| invalid())?;
+        let minutes: i64 = minutes.parse().map_err(|_| invalid())?;
+        return Ok(Duration::hours(hours) + Duration::minutes(minutes));
+    }
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let value: i64 = number.parse().map_err(|_| invalid())?;
+        number.clear();
+        total = total
+            + match c {
+                'h' => Duration::hours(value),
+                'm' => Duration::minutes(value),
+                's' => Duration::seconds(value),
+                _ => return Err(invalid()),
+            };
+    }
+    if !number.is_empty() || input.is_empty() {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+/// Parses a point in time given on the command line, in local time unless it
+/// carries an offset. Accepts `now`, RFC 3339, `YYYY-MM-DD [HH:MM[:SS]]`, or a time
+/// of day combined with `today`, `yesterday`, `<weekday>` or
+/// `last <weekday>` in either order (e.g. `14:00 last tuesday`).
+pub fn parse_datetime(input: &str, now: DateTime<Local>) -> Result<DateTime<Utc>, String> {
+    let input = input.trim();
+    if input.eq_ignore_ascii_case("now") {
+        return Ok(now.with_timezone(&Utc));
+    }
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(input) {
+        return Ok(datetime.with_timezone(&Utc));
+    }
+    for format in [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+    ] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(input, format) {
+            return local_to_utc(datetime);
+        }
+    }
+
+    let mut time = None;
+    let mut date_words = Vec::new();
+    for word in input.split_whitespace() {
+        match parse_time_of_day(word) {
+            Some(parsed) if time.is_none() => time = Some(parsed),
+            _ => date_words.push(word),
+        }
+    }
+    let date = parse_date(&date_words.join(" "), now.date_naive())?;
+    let time = time.unwrap_or(NaiveTime::MIN);
+    local_to_utc(date.and_time(time))
+}
+
+pub fn parse_time_of_day(input: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(input, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(input, "%H:%M"))
+        .ok()
+}
+
+/// Parses `today`, `yesterday`, `YYYY-MM-DD`, `<weekday>` (the most recent
+/// one, today included) or `last <weekday>` (strictly before today). An
+/// empty string means today.
+pub fn parse_date(input: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let input = input.trim().to_lowercase();
+    match input.as_str() {
+        "" | "today" => return Ok(today),
+        "yesterday" => return Ok(today - Duration::days(1)),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&input, "%Y-%m-%d") {
+        return Ok(date);
+    }
+
+    let (weekday, strictly_before) = match input.strip_prefix("last ") {
+        Some(weekday) => (weekday.trim(), true),
+        None => (input.as_str(), false),
+    };
+    let weekday: Weekday = weekday
+        .parse()
+        .map_err(|_| format!("Could not understand the date {:?}", input))?;
+    let mut date = if strictly_before {
+        today - Duration::days(1)
+    } else {
+        today
+    };
+    while date.weekday() != weekday {
+        date -= Duration::days(1);
+    }
+    Ok(date)
+}
+
+pub fn local_to_utc(datetime: NaiveDateTime) -> Result<DateTime<Utc>, String> {
+    Local
+        .from_local_datetime(&datetime)
+        .earliest()
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .ok_or_else(|| format!("{} does not exist in the local time zone", datetime))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::matching;
+
+    fn items() -> Vec<String> {
+        [
+            "fix login (#acme/web)",
+            "Write docs (Uncategorized)",
+            "login page review (#acme)",
+        ]
+        .iter()
+        .map(|item| item.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn matching_keeps_order_and_ignores_case() {
+        assert_eq!(matching(&items(), "LOGIN"), vec![0, 2]);
+        assert_eq!(matching(&items(), "docs"), vec![1]);
+    }
+
+    #[test]
+    fn matching_needs_every_word_in_any_order() {
+        assert_eq!(matching(&items(), "review login"), vec![2]);
+        assert_eq!(matching(&items(), "acme  web"), vec![0]);
+        assert!(matching(&items(), "login docs").is_empty());
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        assert_eq!(matching(&items(), ""), vec![0, 1, 2]);
+        assert_eq!(matching(&items(), "   "), vec![0, 1, 2]);
+    }
+}
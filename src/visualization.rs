@@ -1,17 +1,44 @@
 // src/visualization.rs
 use crate::categorization::Categorization;
-use crate::data::{load_data, Task, TimePeriod};
+use crate::data::{TimeChunk, TimePeriod};
 use chrono::TimeZone;
-use chrono::{DateTime, Duration, NaiveDateTime, Timelike, Utc};
+use chrono::{Duration, NaiveDateTime, Timelike, Utc};
 use chrono_tz::Tz;
 use plotters::prelude::*;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
+/// Draws one row per task, or with `depth` one row per category rolled up to
+/// that many levels below the top (or below `category`, when given).
 pub fn visualize_data(
     time_period: &TimePeriod,
     output_path: &PathBuf,
+    category: Option<&str>,
+    depth: Option<usize>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    println!("Output path: {:?}", output_path); // Debug statement
+    let top = category.map_or(0, |name| Categorization::category_path(name).len());
+    let mut rows: BTreeMap<String, Vec<&TimeChunk>> = BTreeMap::new();
+    for (name, tasks) in &time_period.categorization.categories {
+        if category.is_some_and(|category| !Categorization::is_within(name, category)) {
+            continue;
+        }
+        for task in tasks {
+            let row = match depth {
+                Some(depth) => {
+                    let path = Categorization::category_path(name);
+                    let level = path.len().min(top + depth.max(1));
+                    match name.starts_with('#') {
+                        true => format!("#{}", path[..level].join("/")),
+                        false => name.clone(),
+                    }
+                }
+                None => format!("{} ({})", task.name, name),
+            };
+            rows.entry(row).or_default().extend(&task.time_chunks);
+        }
+    }
+    let rows: Vec<(String, Vec<&TimeChunk>)> = rows.into_iter().collect();
+
     let root = BitMapBackend::new(output_path, (1280, 720)).into_drawing_area();
     root.fill(&WHITE)?;
 
@@ -24,23 +51,23 @@ pub fn visualize_data(
     };
     let max_time = min_time + Duration::days(1);
 
-    let duration = max_time - min_time;
-    let duration_secs = duration.num_seconds() as f64;
-
     let mut chart = ChartBuilder::on(&root)
         .caption("Time Tracker Visualization", ("sans-serif", 40))
         .margin(10)
         .x_label_area_size(30)
         .y_label_area_size(60)
-        .build_cartesian_2d(
-            min_time.timestamp()..max_time.timestamp(),
-            0..time_period.categorization.categories.len(),
-        )?;
+        .build_cartesian_2d(min_time.timestamp()..max_time.timestamp(), 0..rows.len())?;
 
     chart
         .configure_mesh()
         .x_desc("Time")
         .y_desc("Tasks")
+        .y_labels(rows.len())
+        .y_label_formatter(&|row| {
+            rows.get(*row)
+                .map(|(name, _)| name.clone())
+                .unwrap_or_default()
+        })
         .axis_desc_style(("sans-serif", 15))
         .x_labels(10) // Controls the number of labels on the x-axis
         .x_label_formatter(&|timestamp| {
@@ -50,19 +77,10 @@ pub fn visualize_data(
         })
         .draw()?;
 
-    let tasks: Vec<&Task> = time_period
-        .categorization
-        .categories
-        .values()
-        .flat_map(|tasks| tasks.iter())
-        .collect();
-
-    println!("Number of tasks: {}", tasks.len()); // Debug statement
-
     let mut rectangles = vec![];
 
-    for (task_idx, task) in tasks.iter().enumerate() {
-        for time_chunk in &task.time_chunks {
+    for (task_idx, (_, chunks)) in rows.iter().enumerate() {
+        for time_chunk in chunks {
             let color = Palette99::pick(task_idx);
             let start = time_chunk.start_time.timestamp();
             let end = time_chunk
@@ -0,0 +1,164 @@
+mod common;
+
+use common::TestEnv;
+use std::fs;
+
+fn stored_chunks(env: &TestEnv, category: &str, task: &str) -> Vec<(String, String)> {
+    let task = env
+        .stored_task(category, task)
+        .unwrap_or_else(|| panic!("no task {} in {}", task, category));
+    assert_eq!(task["status"], "Stopped");
+    task["time_chunks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|chunk| {
+            (
+                chunk["start_time"].as_str().unwrap().to_string(),
+                chunk["end_time"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+fn chunk(start: &str, end: &str) -> (String, String) {
+    (
+        format!("2024-03-05T{}:00Z", start),
+        format!("2024-03-05T{}:00Z", end),
+    )
+}
+
+#[test]
+fn add_with_from_and_to_files_the_task_under_its_category() {
+    let env = TestEnv::new();
+
+    env.run_ok(&[
+        "add",
+        "Fix login bug #backend",
+        "--date",
+        "2024-03-05",
+        "--from",
+        "09:15",
+        "--to",
+        "10:40",
+    ]);
+
+    assert_eq!(
+        stored_chunks(&env, "#backend", "Fix login bug"),
+        vec![chunk("09:15", "10:40")]
+    );
+}
+
+#[test]
+fn add_with_duration_counts_from_the_start() {
+    let env = TestEnv::new();
+
+    env.run_ok(&[
+        "add",
+        "email",
+        "--date",
+        "2024-03-05",
+        "--from",
+        "08:00",
+        "--duration",
+        "1h25m",
+    ]);
+
+    assert_eq!(
+        stored_chunks(&env, "Uncategorized", "email"),
+        vec![chunk("08:00", "09:25")]
+    );
+}
+
+#[test]
+fn add_refuses_an_entry_that_ends_before_it_starts() {
+    let env = TestEnv::new();
+
+    let output = env.run(&[
+        "add",
+        "email",
+        "--date",
+        "2024-03-05",
+        "--from",
+        "10:00",
+        "--to",
+        "09:00",
+    ]);
+
+    assert!(!output.status.success());
+    assert!(!env.data_file().exists());
+}
+
+#[test]
+fn overlap_without_a_terminal_adds_nothing() {
+    let env = TestEnv::with_meeting();
+    let before = fs::read_to_string(env.data_file()).unwrap();
+
+    let output = env.run_ok(&[
+        "add",
+        "email",
+        "--date",
+        "2024-03-05",
+        "--from",
+        "09:30",
+        "--to",
+        "10:30",
+    ]);
+
+    assert!(output.contains("overlaps 1 existing"), "{}", output);
+    assert_eq!(fs::read_to_string(env.data_file()).unwrap(), before);
+}
+
+#[test]
+fn trim_shrinks_the_new_entry() {
+    let env = TestEnv::with_meeting();
+
+    env.run_ok(&[
+        "add",
+        "email",
+        "--date",
+        "2024-03-05",
+        "--from",
+        "09:30",
+        "--to",
+        "11:30",
+        "--on-overlap",
+        "trim",
+    ]);
+
+    assert_eq!(
+        stored_chunks(&env, "Uncategorized", "email"),
+        vec![chunk("09:30", "10:00"), chunk("11:00", "11:30")]
+    );
+    assert_eq!(
+        stored_chunks(&env, "Uncategorized", "meeting"),
+        vec![chunk("10:00", "11:00")]
+    );
+}
+
+#[test]
+fn split_cuts_the_existing_entry_around_the_new_one() {
+    let env = TestEnv::with_meeting();
+
+    env.run_ok(&[
+        "add",
+        "email",
+        "--date",
+        "2024-03-05",
+        "--from",
+        "10:15",
+        "--to",
+        "10:30",
+        "--on-overlap",
+        "split",
+    ]);
+
+    assert_eq!(
+        stored_chunks(&env, "Uncategorized", "meeting"),
+        vec![chunk("10:00", "10:15"), chunk("10:30", "11:00")]
+    );
+    assert_eq!(
+        stored_chunks(&env, "Uncategorized", "email"),
+        vec![chunk("10:15", "10:30")]
+    );
+}
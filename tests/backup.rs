@@ -0,0 +1,137 @@
+mod common;
+
+use common::TestEnv;
+use std::fs;
+
+/// Snapshot file names in the backups directory, oldest first.
+fn backups(env: &TestEnv) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(env.data_dir().join("backups"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn saves_within_the_interval_share_one_backup() {
+    let env = TestEnv::new();
+
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["stop"]);
+    env.run_ok(&["start", "review"]);
+
+    assert_eq!(backups(&env).len(), 1);
+}
+
+#[test]
+fn list_and_restore_bring_back_an_earlier_state() {
+    let env = TestEnv::new();
+    env.run_ok(&["configure", "--backup-interval", "0"]);
+    env.add("meeting", "2024-03-05", "10:00", "11:00");
+    env.add("lunch", "2024-03-05", "12:00", "13:00");
+
+    let listing = env.run_ok(&["backup", "list"]);
+
+    let lines: Vec<&str> = listing.lines().collect();
+    // Newest first, down to the empty data `configure` saved.
+    assert_eq!(lines.len(), 3, "{}", listing);
+    assert!(
+        lines[0].ends_with("  2 tasks, 02h 00m 00s tracked"),
+        "{}",
+        listing
+    );
+    assert!(
+        lines[1].ends_with("  1 tasks, 01h 00m 00s tracked"),
+        "{}",
+        listing
+    );
+    let older = lines[1].split_whitespace().next().unwrap();
+
+    let output = env.run_ok(&["backup", "restore", older, "--yes"]);
+
+    assert!(
+        output.contains(&format!("Restored backup {}", older)),
+        "{}",
+        output
+    );
+    assert!(env.stored_task("Uncategorized", "meeting").is_some());
+    assert!(env.stored_task("Uncategorized", "lunch").is_none());
+    env.run_ok(&["undo"]);
+    assert!(env.stored_task("Uncategorized", "lunch").is_some());
+}
+
+#[test]
+fn restore_needs_an_unambiguous_id() {
+    let env = TestEnv::new();
+    env.run_ok(&["configure", "--backup-interval", "0"]);
+    env.add("meeting", "2024-03-05", "10:00", "11:00");
+    env.add("lunch", "2024-03-05", "12:00", "13:00");
+
+    let ambiguous = env.run(&["backup", "restore", "2", "--yes"]);
+    assert!(!ambiguous.status.success());
+    assert!(String::from_utf8_lossy(&ambiguous.stderr).contains("3 backups match"));
+
+    let unknown = env.run(&["backup", "restore", "1999", "--yes"]);
+    assert!(!unknown.status.success());
+    assert!(String::from_utf8_lossy(&unknown.stderr).contains("No backup matches"));
+    assert!(env.stored_task("Uncategorized", "lunch").is_some());
+}
+
+#[test]
+fn unreadable_backups_directory_is_reported() {
+    let env = TestEnv::with_meeting();
+    let backup_dir = env.data_dir().join("backups");
+    fs::remove_dir_all(&backup_dir).unwrap();
+    fs::write(&backup_dir, "not a directory").unwrap();
+
+    let output = env.run(&["backup", "list"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.starts_with("Error: Could not list the backups in "),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
+
+#[test]
+fn pruning_keeps_recent_backups_and_the_oldest_of_each_day() {
+    let env = TestEnv::with_meeting();
+    let dir = env.data_dir().join("backups");
+    for old in backups(&env) {
+        fs::remove_file(dir.join(old)).unwrap();
+    }
+    let data = fs::read_to_string(env.data_file()).unwrap();
+    for id in [
+        "20240301T090000000",
+        "20240301T170000000",
+        "20240302T080000000",
+        "20240302T200000000",
+        "20240303T120000000",
+    ] {
+        fs::write(dir.join(format!("tasks-{}.json", id)), &data).unwrap();
+    }
+    env.run_ok(&[
+        "configure",
+        "--keep-recent",
+        "1",
+        "--keep-hourly",
+        "0",
+        "--keep-daily",
+        "3",
+        "--backup-interval",
+        "0",
+    ]);
+
+    env.run_ok(&["start", "coding"]);
+
+    let kept = backups(&env);
+    assert_eq!(kept.len(), 3, "{:?}", kept);
+    // The oldest of the two days before, then the new backup.
+    assert_eq!(kept[0], "tasks-20240302T080000000.json");
+    assert_eq!(kept[1], "tasks-20240303T120000000.json");
+    assert!(!kept[2].starts_with("tasks-2024"), "{:?}", kept);
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use common::{fixture, TestEnv};
+use serde_json::{json, Value};
+use std::fs;
+
+fn starts(task: &Value) -> Vec<&str> {
+    task["time_chunks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|chunk| chunk["start_time"].as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn events_that_took_time_become_chunks() {
+    let env = TestEnv::new();
+    let calendar = fixture("calendar.ics");
+
+    let output = env.run_ok(&[
+        "import",
+        "ics",
+        &calendar,
+        "--exclude",
+        "lunch",
+        "--category",
+        "meetings",
+        "--yes",
+    ]);
+
+    assert!(output.contains("Imported 5 entries."), "{}", output);
+    // Weekly on Mondays and Wednesdays in Berlin time, less the skipped
+    // Wednesday, with the second Monday moved to 10:00.
+    let standup = env.stored_task("#team", "Standup").unwrap();
+    assert_eq!(
+        starts(&standup),
+        [
+            "2024-03-04T08:30:00Z",
+            "2024-03-11T09:00:00Z",
+            "2024-03-13T08:30:00Z"
+        ]
+    );
+    let review = env.stored_task("#acme/web", "Design review").unwrap();
+    assert_eq!(review["tags"], json!(["Client"]));
+    assert_eq!(review["time_chunks"][0]["end_time"], "2024-03-05T14:30:00Z");
+    assert_eq!(
+        review["time_chunks"][0]["notes"][0]["text"],
+        "Went through the new, longer checkout flow with the design team and agreed on next steps."
+    );
+    assert!(env.stored_task("#meetings", "1:1 with Alex").is_some());
+    for skipped in ["Lunch", "Vendor sync", "Focus time", "Offsite", "Planning"] {
+        assert!(!output.contains(skipped), "{}", output);
+    }
+}
+
+#[test]
+fn include_keeps_only_matching_events() {
+    let env = TestEnv::new();
+
+    let output = env.run_ok(&[
+        "import",
+        "ics",
+        &fixture("calendar.ics"),
+        "--include",
+        "STANDUP",
+        "--since",
+        "2024-03-10",
+        "--dry-run",
+    ]);
+
+    assert!(output.contains("2 new entries"), "{}", output);
+    assert!(!output.contains("Design review"), "{}", output);
+}
+
+#[test]
+fn exported_calendar_imports_back_without_losing_time() {
+    let env = TestEnv::new();
+    env.track_sample();
+    env.run_ok(&["note", "--on", "fix login", "retry; then, \"escalate\""]);
+    let calendar = env.root().join("tracked.ics");
+
+    env.run_ok(&[
+        "export",
+        "--format",
+        "ics",
+        "-o",
+        calendar.to_str().unwrap(),
+    ]);
+
+    let written = fs::read_to_string(&calendar).unwrap();
+    assert!(written.starts_with("BEGIN:VCALENDAR\r\n"), "{}", written);
+    assert!(written.contains("\r\nSUMMARY:fix login\r\n"), "{}", written);
+    assert!(
+        written.contains("\r\nCATEGORIES:#acme/web,billable\r\n"),
+        "{}",
+        written
+    );
+    assert!(
+        written.contains("\r\nDESCRIPTION:retry\\; then\\, \"escalate\"\r\n"),
+        "{}",
+        written
+    );
+
+    let other = TestEnv::new();
+    other.run_ok(&["import", "ics", calendar.to_str().unwrap(), "--yes"]);
+    assert_eq!(
+        other.exported_rows(&["task_id"]),
+        env.exported_rows(&["task_id"])
+    );
+}
+
+#[test]
+fn repeat_rules_beyond_the_calendar_import_only_the_first_event() {
+    let env = TestEnv::new();
+    for rule in [
+        "FREQ=DAILY;INTERVAL=999999999",
+        "FREQ=MONTHLY;INTERVAL=4294967295",
+        "FREQ=YEARLY;INTERVAL=3000000000",
+    ] {
+        let calendar = env.root().join("huge.ics");
+        fs::write(
+            &calendar,
+            format!(
+                "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:huge\r\nSUMMARY:Standup\r\n\
+                 DTSTART:20240304T083000Z\r\nDTEND:20240304T084500Z\r\nRRULE:{}\r\n\
+                 END:VEVENT\r\nEND:VCALENDAR\r\n",
+                rule
+            ),
+        )
+        .unwrap();
+
+        let output = env.run(&["import", "ics", calendar.to_str().unwrap(), "--dry-run"]);
+
+        assert!(output.status.success(), "{}", rule);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("Importing only the first \"Standup\""),
+            "{}",
+            stderr
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("1 new entries"), "{}", stdout);
+    }
+}
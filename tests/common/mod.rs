@@ -0,0 +1,151 @@
+// tests/common/mod.rs
+#![allow(dead_code)]
+
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+/// Path of `tests/fixtures/<name>`.
+pub fn fixture(name: &str) -> String {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// The short task ID printed in front of `name` by `list`.
+pub fn listed_id(listing: &str, name: &str) -> String {
+    listing
+        .lines()
+        .find_map(|line| {
+            let (id, rest) = line.split_once(' ')?;
+            rest.starts_with(&format!("{} - ", name))
+                .then(|| id.to_string())
+        })
+        .unwrap_or_else(|| panic!("{} not listed in {}", name, listing))
+}
+
+/// Runs the binary against a throwaway config and data directory.
+pub struct TestEnv {
+    dir: TempDir,
+}
+
+impl TestEnv {
+    pub fn new() -> Self {
+        Self {
+            dir: TempDir::new().unwrap(),
+        }
+    }
+
+    /// A stopped "meeting" with one chunk on 2024-03-05 from 10:00 to 11:00.
+    pub fn with_meeting() -> Self {
+        let env = Self::new();
+        env.add("meeting", "2024-03-05", "10:00", "11:00");
+        env
+    }
+
+    pub fn root(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn data_dir(&self) -> PathBuf {
+        self.dir.path().join("data").join("time_tracker")
+    }
+
+    pub fn data_file(&self) -> PathBuf {
+        self.data_dir().join("tasks.json")
+    }
+
+    /// Copies `tests/fixtures/<name>` into place as the data file.
+    pub fn install_fixture(&self, name: &str) {
+        fs::create_dir_all(self.data_dir()).unwrap();
+        fs::copy(fixture(name), self.data_file()).unwrap();
+    }
+
+    /// Adds a finished entry on `date` from `from` to `to`.
+    pub fn add(&self, description: &str, date: &str, from: &str, to: &str) {
+        self.run_ok(&[
+            "add",
+            description,
+            "--date",
+            date,
+            "--from",
+            from,
+            "--to",
+            to,
+        ]);
+    }
+
+    /// Finished entries over two months: "fix login" twice under
+    /// `#acme/web` (tagged `billable` by the first), a "standup" under
+    /// `#internal` and an uncategorized "write docs" just before midnight.
+    pub fn track_sample(&self) {
+        self.add(
+            "fix login #acme/web #billable",
+            "2024-03-05",
+            "09:00",
+            "10:30",
+        );
+        self.add("standup #internal", "2024-03-05", "11:00", "11:15");
+        self.add("write docs", "2024-03-29", "23:30", "23:59");
+        self.add("fix login #acme/web", "2024-04-02", "09:00", "10:00");
+    }
+
+    /// The rows of the JSON export, each without the `left_out` keys, e.g.
+    /// what another tracker cannot carry over.
+    pub fn exported_rows(&self, left_out: &[&str]) -> Vec<Value> {
+        let rows: Value =
+            serde_json::from_str(&self.run_ok(&["export", "--format", "json"])).unwrap();
+        rows.as_array()
+            .unwrap()
+            .iter()
+            .map(|row| {
+                let mut row = row.clone();
+                let fields = row.as_object_mut().unwrap();
+                for key in left_out {
+                    fields.remove(*key);
+                }
+                row
+            })
+            .collect()
+    }
+
+    /// The stored task `name` in `category`, if there is one.
+    pub fn stored_task(&self, category: &str, name: &str) -> Option<Value> {
+        let contents = fs::read_to_string(self.data_file()).ok()?;
+        let value: Value = serde_json::from_str(&contents).unwrap();
+        value["categorization"]["categories"][category]
+            .as_array()?
+            .iter()
+            .find(|task| task["name"] == name)
+            .cloned()
+    }
+
+    pub fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_time_tracker"))
+            .args(args)
+            .env("HOME", self.dir.path())
+            .env("TZ", "UTC")
+            .env("USER", "tester")
+            .env("XDG_CONFIG_HOME", self.dir.path().join("config"))
+            .env("XDG_DATA_HOME", self.dir.path().join("data"))
+            .output()
+            .unwrap()
+    }
+
+    /// Runs the binary, asserts it succeeded and returns its stdout.
+    pub fn run_ok(&self, args: &[&str]) -> String {
+        let output = self.run(args);
+        assert!(
+            output.status.success(),
+            "{:?} failed:\n{}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    }
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use common::TestEnv;
+
+fn chunk_times(env: &TestEnv, category: &str, task: &str) -> Vec<(String, String)> {
+    let task = env
+        .stored_task(category, task)
+        .unwrap_or_else(|| panic!("no task {} in {}", task, category));
+    task["time_chunks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|chunk| {
+            (
+                chunk["start_time"].as_str().unwrap()[11..16].to_string(),
+                chunk["end_time"].as_str().unwrap_or("open")[11..16].to_string(),
+            )
+        })
+        .collect()
+}
+
+fn times(start: &str, end: &str) -> (String, String) {
+    (start.to_string(), end.to_string())
+}
+
+#[test]
+fn edit_changes_start_and_end_on_the_chunk_day() {
+    let env = TestEnv::with_meeting();
+
+    env.run_ok(&["edit", "meeting", "--start", "09:30", "--end", "10:45"]);
+
+    assert_eq!(
+        chunk_times(&env, "Uncategorized", "meeting"),
+        vec![times("09:30", "10:45")]
+    );
+}
+
+#[test]
+fn edit_refuses_an_end_before_the_start() {
+    let env = TestEnv::with_meeting();
+
+    let output = env.run(&["edit", "meeting", "--end", "09:00"]);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        chunk_times(&env, "Uncategorized", "meeting"),
+        vec![times("10:00", "11:00")]
+    );
+}
+
+#[test]
+fn split_at_cuts_a_chunk_in_two() {
+    let env = TestEnv::with_meeting();
+
+    env.run_ok(&["edit", "meeting", "--chunk", "1", "--split-at", "10:20"]);
+
+    assert_eq!(
+        chunk_times(&env, "Uncategorized", "meeting"),
+        vec![times("10:00", "10:20"), times("10:20", "11:00")]
+    );
+}
+
+#[test]
+fn move_to_hands_the_chunk_to_another_task() {
+    let env = TestEnv::with_meeting();
+    env.run_ok(&["edit", "meeting", "--split-at", "10:20"]);
+
+    env.run_ok(&[
+        "edit",
+        "meeting",
+        "--chunk",
+        "2",
+        "--move-to",
+        "review #backend",
+    ]);
+
+    assert_eq!(
+        chunk_times(&env, "Uncategorized", "meeting"),
+        vec![times("10:00", "10:20")]
+    );
+    assert_eq!(
+        chunk_times(&env, "#backend", "review"),
+        vec![times("10:20", "11:00")]
+    );
+}
+
+#[test]
+fn deleting_the_last_chunk_removes_the_task_and_undo_restores_it() {
+    let env = TestEnv::with_meeting();
+
+    env.run_ok(&["delete", "meeting", "--chunk", "1"]);
+    assert!(env.stored_task("Uncategorized", "meeting").is_none());
+
+    env.run_ok(&["undo"]);
+    assert_eq!(
+        chunk_times(&env, "Uncategorized", "meeting"),
+        vec![times("10:00", "11:00")]
+    );
+}
+
+#[test]
+fn edits_must_not_overlap_the_running_task() {
+    let env = TestEnv::with_meeting();
+    env.run_ok(&["start", "coding"]);
+
+    let output = env.run(&[
+        "edit",
+        "meeting",
+        "--start",
+        "2024-03-05 10:00",
+        "--end",
+        "now",
+    ]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("\"coding\""));
+    assert_eq!(
+        chunk_times(&env, "Uncategorized", "meeting"),
+        vec![times("10:00", "11:00")]
+    );
+}
+
+#[test]
+fn deleting_the_chunk_before_a_pause_stops_the_task() {
+    let env = TestEnv::with_meeting();
+    env.run_ok(&["start", "meeting"]);
+    env.run_ok(&["pause"]);
+
+    env.run_ok(&["edit", "meeting", "--delete"]);
+
+    let task = env.stored_task("Uncategorized", "meeting").unwrap();
+    assert_eq!(task["status"], "Stopped");
+    assert_eq!(task["pauses"].as_array().unwrap().len(), 0);
+    assert_eq!(
+        chunk_times(&env, "Uncategorized", "meeting"),
+        vec![times("10:00", "11:00")]
+    );
+    let output = env.run_ok(&["resume"]);
+    assert!(output.contains("No paused task found."), "{}", output);
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use common::{fixture, TestEnv};
+use std::fs;
+
+#[test]
+fn log_lists_every_change_with_who_made_it() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["pause"]);
+    env.run_ok(&["stop", "write docs"]);
+
+    let log = env.run_ok(&["log"]);
+
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), 3, "{}", log);
+    assert!(lines[0].starts_with("#1 "), "{}", log);
+    assert!(
+        lines[0].ends_with(" tester start \"write docs\""),
+        "{}",
+        log
+    );
+    assert!(lines[1].ends_with(" pause \"write docs\" (Uncategorized)"));
+    assert!(lines[2].ends_with(" stop \"write docs\" (Uncategorized)"));
+    let last = env.run_ok(&["log", "--limit", "1"]);
+    assert!(last.starts_with("#3 "), "{}", last);
+}
+
+#[test]
+fn data_file_is_rebuilt_by_replaying_the_log() {
+    let env = TestEnv::new();
+    env.track_sample();
+    let before = env.exported_rows(&[]);
+
+    fs::remove_file(env.data_file()).unwrap();
+
+    assert_eq!(env.exported_rows(&[]), before);
+}
+
+#[test]
+fn log_takes_precedence_over_an_edited_data_file() {
+    let env = TestEnv::with_meeting();
+    let contents = fs::read_to_string(env.data_file()).unwrap();
+    fs::write(env.data_file(), contents.replace("meeting", "lunch")).unwrap();
+
+    let listing = env.run_ok(&["list"]);
+
+    assert!(listing.contains("meeting"), "{}", listing);
+    assert!(!listing.contains("lunch"), "{}", listing);
+}
+
+#[test]
+fn torn_last_line_of_the_log_is_dropped() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    let log = env.data_dir().join("tasks.json.events");
+    let complete = fs::read_to_string(&log).unwrap();
+    fs::write(&log, format!("{}{{\"id\":2,\"timest", complete)).unwrap();
+    // Make `status` read the data rather than its summary.
+    fs::remove_file(env.data_dir().join("tasks.json.status")).unwrap();
+
+    let output = env.run(&["status", "--format", "{status} {name}"]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "running write docs\n"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unfinished last line"), "{}", stderr);
+    assert_eq!(fs::read_to_string(&log).unwrap(), complete);
+
+    env.run_ok(&["stop"]);
+    let log = env.run_ok(&["log"]);
+    assert!(log.lines().nth(1).unwrap().starts_with("#2 "), "{}", log);
+}
+
+#[test]
+fn complete_last_line_without_its_newline_is_kept() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    let log = env.data_dir().join("tasks.json.events");
+    let complete = fs::read_to_string(&log).unwrap();
+    fs::write(&log, complete.trim_end()).unwrap();
+
+    env.run_ok(&["stop"]);
+
+    let log = env.run_ok(&["log"]);
+    assert_eq!(log.lines().count(), 2, "{}", log);
+    assert_eq!(
+        env.run_ok(&["status", "--format", "{status}"]).trim_end(),
+        "idle"
+    );
+}
+
+#[test]
+fn corrupt_data_file_is_rebuilt_from_the_log() {
+    let env = TestEnv::with_meeting();
+    fs::write(env.data_file(), "{\"version\":6,").unwrap();
+
+    let output = env.run(&["list"]);
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("rebuilt it from the event log"),
+        "{}",
+        stderr
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("meeting"));
+    assert!(env.stored_task("Uncategorized", "meeting").is_some());
+    assert!(!env.data_dir().join("tasks.json.recovery").exists());
+    let quarantined = fs::read_dir(env.data_dir())
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .any(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("tasks.json.corrupt-")
+        });
+    assert!(quarantined);
+}
+
+#[test]
+fn recover_records_the_restored_data_in_the_log() {
+    let env = TestEnv::new();
+    fs::create_dir_all(env.data_dir()).unwrap();
+    fs::write(env.data_file(), "{\"version\":6,").unwrap();
+    // Without a log to rebuild from, nothing runs until recovery.
+    assert!(!env.run(&["list"]).status.success());
+    assert!(!env.run(&["start", "coding"]).status.success());
+
+    let output = env.run_ok(&["recover", "--restore", &fixture("v6.json")]);
+
+    assert!(output.contains("Restored data"), "{}", output);
+    let listing = env.run_ok(&["list"]);
+    assert!(listing.contains("fix login"), "{}", listing);
+    assert!(env.run_ok(&["log"]).contains("load snapshot of 2 tasks"));
+    // The restored data survives the log being replayed.
+    fs::remove_file(env.data_file()).unwrap();
+    assert!(env.run_ok(&["list"]).contains("fix login"));
+}
+
+#[test]
+fn recover_can_start_fresh() {
+    let env = TestEnv::new();
+    fs::create_dir_all(env.data_dir()).unwrap();
+    fs::write(env.data_file(), "not json").unwrap();
+    assert!(!env.run(&["list"]).status.success());
+
+    let refused = env.run(&["recover"]);
+    assert!(!refused.status.success());
+
+    let output = env.run_ok(&["recover", "--start-fresh"]);
+    assert!(output.contains("Starting with empty data"), "{}", output);
+    env.run_ok(&["start", "coding"]);
+    assert!(env.stored_task("Uncategorized", "coding").is_some());
+    let again = env.run_ok(&["recover", "--start-fresh"]);
+    assert!(again.contains("No recovery pending"), "{}", again);
+}
+
+#[test]
+fn at_shows_what_was_running_including_time_added_later() {
+    let env = TestEnv::with_meeting();
+
+    let during = env.run_ok(&["at", "2024-03-05 10:30"]);
+    assert_eq!(
+        during,
+        "At 2024-03-05 10:30: running \"meeting\" (Uncategorized) since 2024-03-05 10:00\n"
+    );
+    let after = env.run_ok(&["at", "2024-03-05 11:00"]);
+    assert_eq!(after, "At 2024-03-05 11:00: nothing was running\n");
+}
+
+#[test]
+fn at_shows_paused_tasks() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["pause"]);
+
+    let now = env.run_ok(&["at", "now"]);
+
+    assert!(now.contains(": paused \"write docs\""), "{}", now);
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::TestEnv;
+use serde_json::{json, Value};
+use std::fs;
+
+#[test]
+fn csv_has_one_row_per_chunk_in_a_fixed_column_order() {
+    let env = TestEnv::new();
+    env.track_sample();
+
+    let csv = env.run_ok(&["export"]);
+
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "task_id,name,category,tags,start,end,duration,notes"
+    );
+    assert_eq!(lines.len(), 5, "{}", csv);
+    assert!(lines[1].ends_with(
+        ",fix login,#acme/web,billable,2024-03-05T09:00:00Z,2024-03-05T10:30:00Z,5400,"
+    ));
+    assert!(lines[2].contains(",standup,#internal,,2024-03-05T11:00:00Z,"));
+}
+
+#[test]
+fn json_and_ndjson_carry_the_same_rows() {
+    let env = TestEnv::new();
+    env.track_sample();
+
+    let json_rows: Value =
+        serde_json::from_str(&env.run_ok(&["export", "--format", "json"])).unwrap();
+    let ndjson = env.run_ok(&["export", "--format", "ndjson"]);
+    let ndjson_rows: Vec<Value> = ndjson
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert_eq!(json_rows, Value::Array(ndjson_rows));
+    let standup = &json_rows[1];
+    assert_eq!(standup["name"], "standup");
+    assert_eq!(standup["tags"], json!([]));
+    assert_eq!(standup["duration"], 900);
+    let keys: Vec<&String> = standup.as_object().unwrap().keys().collect();
+    assert_eq!(keys.len(), 8);
+}
+
+#[test]
+fn range_and_category_filters_pick_chunks_by_start() {
+    let env = TestEnv::new();
+    env.track_sample();
+
+    let march_5 = env.run_ok(&[
+        "export",
+        "--format",
+        "ndjson",
+        "--from",
+        "2024-03-05",
+        "--to",
+        "2024-03-06",
+        "--category",
+        "acme",
+    ]);
+
+    let rows: Vec<Value> = march_5
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(rows.len(), 1, "{}", march_5);
+    assert_eq!(rows[0]["start"], "2024-03-05T09:00:00Z");
+}
+
+#[test]
+fn notes_and_running_chunks_are_exported_to_a_file() {
+    let env = TestEnv::new();
+    env.track_sample();
+    env.run_ok(&["note", "--on", "fix login", "sent, \"final\" draft"]);
+    env.run_ok(&["start", "write", "docs"]);
+    let path = env.root().join("out.csv");
+
+    let output = env.run_ok(&["export", "-o", path.to_str().unwrap()]);
+
+    assert!(output.contains("Exported 5 chunks"), "{}", output);
+    let csv = fs::read_to_string(&path).unwrap();
+    assert!(
+        csv.contains(",2024-04-02T10:00:00Z,3600,\"sent, \"\"final\"\" draft\""),
+        "{}",
+        csv
+    );
+    let running = csv.lines().last().unwrap();
+    assert!(
+        running.contains(",write docs,Uncategorized,,"),
+        "{}",
+        running
+    );
+    assert!(running.contains("Z,,"), "{}", running);
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use common::TestEnv;
+
+/// Adds a finished entry from `from` to `to` hours before the hour that
+/// just started, so consecutive entries line up exactly.
+fn add_hours_ago(env: &TestEnv, description: &str, from: i64, to: i64) {
+    let now = Utc::now().timestamp();
+    let hour = Utc.timestamp_opt(now - now % 3600, 0).unwrap();
+    let at = |hours| (hour - Duration::hours(hours)).to_rfc3339();
+    env.run_ok(&["add", description, "--from", &at(from), "--to", &at(to)]);
+}
+
+fn acme(env: &TestEnv) {
+    add_hours_ago(env, "header #acme/website/frontend", 3, 1);
+    add_hours_ago(env, "api #acme/website/backend", 4, 3);
+    add_hours_ago(env, "ads #acme/marketing", 5, 4);
+}
+
+/// The lines of the summary's category tree.
+fn tree(summary: &str) -> Vec<&str> {
+    summary
+        .lines()
+        .skip_while(|line| *line != "Category tree:")
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .collect()
+}
+
+#[test]
+fn summary_rolls_time_up_the_tree() {
+    let env = TestEnv::new();
+    acme(&env);
+
+    let summary = env.run_ok(&["summary", "day"]);
+
+    assert_eq!(
+        tree(&summary),
+        vec![
+            "#acme: 04h 00m 00s",
+            "  marketing: 01h 00m 00s",
+            "  website: 03h 00m 00s",
+            "    backend: 01h 00m 00s",
+            "    frontend: 02h 00m 00s",
+        ]
+    );
+}
+
+#[test]
+fn summary_drills_down_to_a_subtree_and_depth() {
+    let env = TestEnv::new();
+    acme(&env);
+
+    let website = env.run_ok(&["summary", "day", "--category", "acme/website"]);
+    assert_eq!(
+        tree(&website),
+        vec![
+            "#acme/website: 03h 00m 00s",
+            "  backend: 01h 00m 00s",
+            "  frontend: 02h 00m 00s",
+        ]
+    );
+    assert!(!website.contains("marketing"), "{}", website);
+
+    let top = env.run_ok(&["summary", "day", "--depth", "0"]);
+    assert_eq!(tree(&top), vec!["#acme: 04h 00m 00s"]);
+}
+
+#[test]
+fn a_tag_filter_matches_nested_categories() {
+    let env = TestEnv::new();
+    acme(&env);
+
+    let listing = env.run_ok(&["list", "--tag", "acme/website"]);
+
+    assert!(listing.contains("header"), "{}", listing);
+    assert!(listing.contains("api"), "{}", listing);
+    assert!(!listing.contains("ads"), "{}", listing);
+}
+
+#[test]
+fn renaming_a_category_moves_its_subcategories() {
+    let env = TestEnv::new();
+    acme(&env);
+
+    env.run_ok(&["category", "rename", "#acme/website", "#acme/site"]);
+
+    assert!(env.stored_task("#acme/site/frontend", "header").is_some());
+    assert!(env.stored_task("#acme/site/backend", "api").is_some());
+    assert!(env.stored_task("#acme/marketing", "ads").is_some());
+
+    let output = env.run(&["category", "rename", "#acme", "#acme/old"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn category_totals_only_count_the_period() {
+    let env = TestEnv::new();
+    add_hours_ago(&env, "header #acme/website/frontend", 49, 47);
+    add_hours_ago(&env, "header #acme/website/frontend", 3, 1);
+
+    let summary = env.run_ok(&["summary", "day"]);
+
+    assert!(
+        summary.contains("Category: \"#acme/website/frontend\"\nTotal duration: 02h 00m 00s"),
+        "{}",
+        summary
+    );
+    assert_eq!(tree(&summary)[0], "#acme: 02h 00m 00s");
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::{listed_id, TestEnv};
+use std::fs;
+
+#[test]
+fn ids_are_stable_across_commands_and_backends() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "coding"]);
+    let id = listed_id(&env.run_ok(&["list"]), "coding");
+
+    assert!(env
+        .run_ok(&["status"])
+        .starts_with(&format!("{} coding", id)));
+    env.run_ok(&["stop"]);
+    assert_eq!(listed_id(&env.run_ok(&["list"]), "coding"), id);
+
+    env.run_ok(&["configure", "--backend", "sqlite"]);
+    assert_eq!(listed_id(&env.run_ok(&["list"]), "coding"), id);
+}
+
+#[test]
+fn edit_accepts_a_chunk_id_prefix() {
+    let env = TestEnv::with_meeting();
+    let task = env.stored_task("Uncategorized", "meeting").unwrap();
+    let chunk_id = task["time_chunks"][0]["id"].as_str().unwrap();
+
+    env.run_ok(&["edit", &chunk_id[..8], "--start", "09:30"]);
+
+    let task = env.stored_task("Uncategorized", "meeting").unwrap();
+    assert_eq!(task["time_chunks"][0]["id"], chunk_id);
+    assert_eq!(task["time_chunks"][0]["start_time"], "2024-03-05T09:30:00Z");
+}
+
+#[test]
+fn delete_accepts_a_task_id_prefix() {
+    let env = TestEnv::with_meeting();
+    let id = listed_id(&env.run_ok(&["list"]), "meeting");
+
+    env.run_ok(&["delete", &id]);
+
+    assert!(env.stored_task("Uncategorized", "meeting").is_none());
+}
+
+#[test]
+fn events_logged_before_ids_get_the_same_ids_on_every_replay() {
+    let env = TestEnv::new();
+    fs::create_dir_all(env.data_dir()).unwrap();
+    fs::write(
+        env.data_dir().join("tasks.json.events"),
+        concat!(
+            r#"{"id":1,"timestamp":"2024-03-05T10:00:00Z","user":"old","action":{"type":"Start","description":"legacy","at":"2024-03-05T10:00:00Z"}}"#,
+            "\n",
+            r#"{"id":2,"timestamp":"2024-03-05T11:00:00Z","user":"old","action":{"type":"Stop","category":"Uncategorized","task":"legacy","at":"2024-03-05T11:00:00Z"}}"#,
+            "\n",
+        ),
+    )
+    .unwrap();
+
+    let first = listed_id(&env.run_ok(&["list"]), "legacy");
+    env.run_ok(&["start", "coding"]);
+    let second = listed_id(&env.run_ok(&["list"]), "legacy");
+
+    assert_eq!(first, second);
+}
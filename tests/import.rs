@@ -0,0 +1,132 @@
+mod common;
+
+use common::{fixture, TestEnv};
+use serde_json::json;
+use std::fs;
+
+fn import(env: &TestEnv, source: &str, file: &str, extra: &[&str]) -> String {
+    let file = fixture(file);
+    let mut args = vec!["import", "--from", source, file.as_str()];
+    args.extend_from_slice(extra);
+    env.run_ok(&args)
+}
+
+#[test]
+fn toggl_rows_map_to_categories_tags_and_chunks() {
+    let env = TestEnv::new();
+
+    let output = import(&env, "toggl", "toggl.csv", &["--yes"]);
+
+    assert!(output.contains("Imported 2 entries."), "{}", output);
+    let task = env.stored_task("#Acme-Corp/Web-Site", "Fix login").unwrap();
+    assert_eq!(task["tags"], json!(["billable", "bug-fix", "urgent"]));
+    assert_eq!(task["time_chunks"][0]["start_time"], "2024-03-05T09:00:00Z");
+    assert_eq!(task["time_chunks"][0]["end_time"], "2024-03-05T10:30:00Z");
+    assert!(env.stored_task("#Internal", "Standup").is_some());
+}
+
+#[test]
+fn dry_run_lists_the_changes_without_making_them() {
+    let env = TestEnv::new();
+
+    let output = import(&env, "toggl", "toggl.csv", &["--dry-run"]);
+
+    assert!(
+        output.contains(
+            "+ 2024-03-05 09:00 - 10:30 Fix login (#Acme-Corp/Web-Site) #billable #bug-fix #urgent"
+        ),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("2 new entries, 01h 45m 00s in total."),
+        "{}",
+        output
+    );
+    assert!(env.stored_task("#Internal", "Standup").is_none());
+
+    let unconfirmed = import(&env, "toggl", "toggl.csv", &[]);
+    assert!(unconfirmed.contains("Nothing imported"), "{}", unconfirmed);
+    assert!(env.stored_task("#Internal", "Standup").is_none());
+}
+
+#[test]
+fn importing_the_same_rows_again_skips_them() {
+    let env = TestEnv::new();
+    import(&env, "toggl", "toggl.csv", &["--yes"]);
+
+    let again = import(&env, "toggl", "toggl.csv", &["--yes"]);
+
+    assert!(again.contains("Skipping 2 entries"), "{}", again);
+    assert!(again.contains("Nothing to import."), "{}", again);
+    let task = env.stored_task("#Acme-Corp/Web-Site", "Fix login").unwrap();
+    assert_eq!(task["time_chunks"].as_array().unwrap().len(), 1);
+
+    import(&env, "clockify", "clockify.csv", &["--yes"]);
+    let task = env.stored_task("#Acme-Corp/Web-Site", "Fix login").unwrap();
+    assert_eq!(task["time_chunks"].as_array().unwrap().len(), 2);
+    assert_eq!(task["time_chunks"][1]["start_time"], "2024-03-06T14:00:00Z");
+    assert_eq!(
+        task["tags"],
+        json!(["Backend", "billable", "bug-fix", "urgent"])
+    );
+}
+
+#[test]
+fn harvest_days_are_laid_out_from_nine_and_undo_in_one_step() {
+    let env = TestEnv::new();
+
+    import(&env, "harvest", "harvest.csv", &["--yes"]);
+
+    let mockups = env.stored_task("#Acme-Corp/Web-Site", "Mockups").unwrap();
+    assert_eq!(
+        mockups["time_chunks"][0]["start_time"],
+        "2024-03-07T09:00:00Z"
+    );
+    assert_eq!(
+        mockups["time_chunks"][0]["end_time"],
+        "2024-03-07T10:30:00Z"
+    );
+    assert_eq!(
+        mockups["time_chunks"][0]["notes"][0]["text"],
+        "Mockups\nShared with the team"
+    );
+    let meetings = env.stored_task("#Acme-Corp/Web-Site", "Meetings").unwrap();
+    assert_eq!(
+        meetings["time_chunks"][0]["start_time"],
+        "2024-03-07T10:30:00Z"
+    );
+
+    env.run_ok(&["undo"]);
+    assert!(env.stored_task("#Acme-Corp/Web-Site", "Mockups").is_none());
+    assert!(env.stored_task("#Acme-Corp/Web-Site", "Meetings").is_none());
+}
+
+#[test]
+fn harvest_rows_added_to_a_day_import_without_duplicating_the_rest() {
+    let env = TestEnv::new();
+    import(&env, "harvest", "harvest.csv", &["--yes"]);
+    let original = fs::read_to_string(fixture("harvest.csv")).unwrap();
+    let (header, rows) = original.split_once('\n').unwrap();
+    let file = env.root().join("harvest.csv");
+    fs::write(
+        &file,
+        format!(
+            "{}\n2024-03-07,Acme Corp,Web Site,,Meetings,,0.5,0.5,No,No,Ada,Lovelace\n{}",
+            header, rows
+        ),
+    )
+    .unwrap();
+
+    let output = env.run_ok(&["import", "harvest", file.to_str().unwrap(), "--yes"]);
+
+    assert!(output.contains("Skipping 2 entries"), "{}", output);
+    assert!(output.contains("Imported 1 entries."), "{}", output);
+    let meetings = env.stored_task("#Acme-Corp/Web-Site", "Meetings").unwrap();
+    assert_eq!(meetings["time_chunks"].as_array().unwrap().len(), 2);
+    let mockups = env.stored_task("#Acme-Corp/Web-Site", "Mockups").unwrap();
+    assert_eq!(mockups["time_chunks"].as_array().unwrap().len(), 1);
+
+    let again = env.run_ok(&["import", "harvest", file.to_str().unwrap(), "--yes"]);
+    assert!(again.contains("Nothing to import."), "{}", again);
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::TestEnv;
+use serde_json::{json, Value};
+use std::fs;
+
+#[test]
+fn timewarrior_export_imports_back_without_losing_time() {
+    let env = TestEnv::new();
+    env.track_sample();
+    env.run_ok(&["note", "--on", "fix login", "sent the \"final\" patch"]);
+    let dir = env.root().join("timew");
+    fs::create_dir(&dir).unwrap();
+
+    let output = env.run_ok(&[
+        "export",
+        "--format",
+        "timewarrior",
+        "-o",
+        dir.to_str().unwrap(),
+    ]);
+
+    assert!(
+        output.contains("Exported 4 chunks to 2 files"),
+        "{}",
+        output
+    );
+    let march = fs::read_to_string(dir.join("2024-03.data")).unwrap();
+    assert!(
+        march
+            .contains("inc 20240305T090000Z - 20240305T103000Z # \"fix login\" #acme/web billable"),
+        "{}",
+        march
+    );
+    assert!(dir.join("2024-04.data").exists());
+
+    let other = TestEnv::new();
+    let imported = other.run_ok(&["import", "timewarrior", dir.to_str().unwrap(), "--yes"]);
+    assert!(imported.contains("Imported 4 entries."), "{}", imported);
+    assert_eq!(
+        other.exported_rows(&["task_id"]),
+        env.exported_rows(&["task_id"])
+    );
+}
+
+#[test]
+fn watson_export_imports_back_without_losing_time() {
+    let env = TestEnv::new();
+    env.track_sample();
+    let frames = env.root().join("frames");
+
+    env.run_ok(&[
+        "export",
+        "--format",
+        "watson",
+        "-o",
+        frames.to_str().unwrap(),
+    ]);
+
+    let written: Value = serde_json::from_str(&fs::read_to_string(&frames).unwrap()).unwrap();
+    assert_eq!(written[0][2], "acme/web");
+    assert_eq!(written[0][4], json!(["fix login", "billable"]));
+    assert_eq!(written[2][2], "Uncategorized");
+
+    let other = TestEnv::new();
+    let imported = other.run_ok(&["import", "watson", frames.to_str().unwrap(), "--yes"]);
+    assert!(imported.contains("Imported 4 entries."), "{}", imported);
+    // Watson has no notes.
+    assert_eq!(
+        other.exported_rows(&["task_id", "notes"]),
+        env.exported_rows(&["task_id", "notes"])
+    );
+
+    let again = other.run_ok(&["import", "watson", frames.to_str().unwrap(), "--yes"]);
+    assert!(again.contains("Nothing to import."), "{}", again);
+}
+
+#[test]
+fn timewarrior_tags_and_annotations_map_onto_tasks() {
+    let env = TestEnv::new();
+    let data = env.root().join("2024-03.data");
+    fs::write(
+        &data,
+        r##"inc 20240305T090000Z - 20240305T101500Z # "client call" #acme "follow up" # "agreed on \"v2\""
+inc 20240305T110000Z - 20240305T113000Z # "#research/rust"
+inc 20240305T120000Z
+"##,
+    )
+    .unwrap();
+
+    let output = env.run_ok(&["import", "timew", data.to_str().unwrap(), "--yes"]);
+
+    assert!(output.contains("Imported 2 entries."), "{}", output);
+    let call = env.stored_task("#acme", "client call").unwrap();
+    assert_eq!(call["tags"], json!(["follow-up"]));
+    assert_eq!(call["time_chunks"][0]["end_time"], "2024-03-05T10:15:00Z");
+    assert_eq!(
+        call["time_chunks"][0]["notes"][0]["text"],
+        "agreed on \"v2\""
+    );
+    assert!(env.stored_task("#research/rust", "rust").is_some());
+}
@@ -0,0 +1,165 @@
+mod common;
+
+use common::TestEnv;
+use serde_json::Value;
+use std::fs;
+
+const CURRENT_VERSION: u64 = 6;
+
+fn stored_version(env: &TestEnv) -> Option<u64> {
+    let contents = fs::read_to_string(env.data_file()).unwrap();
+    let value: Value = serde_json::from_str(&contents).unwrap();
+    value.get("version").and_then(Value::as_u64)
+}
+
+fn assert_fixture_tasks(listing: &str) {
+    assert!(listing.contains("fix login"), "{}", listing);
+    assert!(listing.contains("email"), "{}", listing);
+    assert!(listing.contains("01:45:00"), "{}", listing);
+    assert!(listing.contains("00:20:00"), "{}", listing);
+}
+
+#[test]
+fn v1_file_is_upgraded_and_backed_up() {
+    let env = TestEnv::new();
+    env.install_fixture("v1.json");
+    let original = fs::read_to_string(env.data_file()).unwrap();
+
+    let listing = env.run_ok(&["list"]);
+
+    assert_fixture_tasks(&listing);
+    assert_eq!(stored_version(&env), Some(CURRENT_VERSION));
+    let backup = env.data_dir().join("tasks.json.v1.bak");
+    assert_eq!(fs::read_to_string(backup).unwrap(), original);
+}
+
+#[test]
+fn v2_file_gains_empty_pauses() {
+    let env = TestEnv::new();
+    env.install_fixture("v2.json");
+    let original = fs::read_to_string(env.data_file()).unwrap();
+
+    let listing = env.run_ok(&["list"]);
+
+    assert_fixture_tasks(&listing);
+    assert_eq!(stored_version(&env), Some(CURRENT_VERSION));
+    let contents = fs::read_to_string(env.data_file()).unwrap();
+    let value: Value = serde_json::from_str(&contents).unwrap();
+    let email = &value["categorization"]["categories"]["Uncategorized"][0];
+    assert_eq!(email["pauses"], Value::Array(Vec::new()));
+    let backup = env.data_dir().join("tasks.json.v2.bak");
+    assert_eq!(fs::read_to_string(backup).unwrap(), original);
+}
+
+/// The IDs of both fixture tasks and their chunks, in a fixed order.
+fn task_ids(env: &TestEnv) -> Vec<String> {
+    [("#backend", "fix login"), ("Uncategorized", "email")]
+        .iter()
+        .flat_map(|(category, name)| {
+            let task = env.stored_task(category, name).unwrap();
+            let mut ids = vec![task["id"].as_str().unwrap().to_string()];
+            for chunk in task["time_chunks"].as_array().unwrap() {
+                ids.push(chunk["id"].as_str().unwrap().to_string());
+            }
+            ids
+        })
+        .collect()
+}
+
+#[test]
+fn v3_file_gains_the_same_ids_every_time() {
+    let first = TestEnv::new();
+    first.install_fixture("v3.json");
+    let second = TestEnv::new();
+    second.install_fixture("v3.json");
+
+    assert_fixture_tasks(&first.run_ok(&["list"]));
+    second.run_ok(&["list"]);
+
+    let ids = task_ids(&first);
+    assert_eq!(ids.len(), 5);
+    assert!(ids.iter().all(|id| id.len() == 16), "{:?}", ids);
+    assert_eq!(ids, task_ids(&second));
+    assert_eq!(stored_version(&first), Some(CURRENT_VERSION));
+    assert!(first.data_dir().join("tasks.json.v3.bak").exists());
+}
+
+#[test]
+fn v4_file_gains_empty_tags_and_keeps_its_ids() {
+    let env = TestEnv::new();
+    env.install_fixture("v4.json");
+
+    assert_fixture_tasks(&env.run_ok(&["list"]));
+
+    assert_eq!(stored_version(&env), Some(CURRENT_VERSION));
+    let task = env.stored_task("#backend", "fix login").unwrap();
+    assert_eq!(task["tags"], Value::Array(Vec::new()));
+    assert_eq!(task["id"], "49c147dd215da3dd");
+    assert!(env.data_dir().join("tasks.json.v4.bak").exists());
+}
+
+#[test]
+fn v5_file_gains_empty_notes() {
+    let env = TestEnv::new();
+    env.install_fixture("v5.json");
+
+    assert_fixture_tasks(&env.run_ok(&["list"]));
+
+    assert_eq!(stored_version(&env), Some(CURRENT_VERSION));
+    let task = env.stored_task("Uncategorized", "email").unwrap();
+    assert_eq!(task["notes"], Value::Array(Vec::new()));
+    assert!(task["time_chunks"][0].get("notes").is_none());
+    assert!(env.data_dir().join("tasks.json.v5.bak").exists());
+}
+
+#[test]
+fn v6_file_loads_without_migration() {
+    let env = TestEnv::new();
+    env.install_fixture("v6.json");
+
+    let listing = env.run_ok(&["list"]);
+
+    assert_fixture_tasks(&listing);
+    assert!(!env.data_dir().join("tasks.json.v6.bak").exists());
+}
+
+#[test]
+fn upgraded_file_loads_again_unchanged() {
+    let env = TestEnv::new();
+    env.install_fixture("v1.json");
+    env.run_ok(&["list"]);
+    let upgraded = fs::read_to_string(env.data_file()).unwrap();
+
+    assert_fixture_tasks(&env.run_ok(&["list"]));
+    assert_eq!(fs::read_to_string(env.data_file()).unwrap(), upgraded);
+}
+
+#[test]
+fn newer_schema_version_is_refused() {
+    let env = TestEnv::new();
+    env.install_fixture("v6.json");
+    let contents = fs::read_to_string(env.data_file())
+        .unwrap()
+        .replace("\"version\":6", "\"version\":999");
+    fs::write(env.data_file(), &contents).unwrap();
+
+    let output = env.run(&["list"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("schema version 999"));
+    assert_eq!(fs::read_to_string(env.data_file()).unwrap(), contents);
+}
+
+#[test]
+fn upgrade_notice_stays_out_of_exported_data() {
+    let env = TestEnv::new();
+    env.install_fixture("v5.json");
+
+    let output = env.run(&["export", "--format", "json"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let rows: Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 3, "{}", stdout);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Upgraded"));
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::TestEnv;
+
+#[test]
+fn note_goes_on_the_running_task() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "review", "#web"]);
+
+    env.run_ok(&["note", "blocked on review"]);
+
+    let task = env.stored_task("#web", "review").unwrap();
+    assert_eq!(task["notes"][0]["text"], "blocked on review");
+    let status = env.run_ok(&["status"]);
+    assert!(status.contains(": blocked on review"), "{}", status);
+    let listing = env.run_ok(&["list"]);
+    assert!(listing.contains(": blocked on review"), "{}", listing);
+}
+
+#[test]
+fn note_without_an_active_task_fails() {
+    let env = TestEnv::new();
+    env.add("meeting #acme", "2024-03-05", "10:00", "11:00");
+
+    let output = env.run(&["note", "hello"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--on"));
+}
+
+#[test]
+fn note_on_a_chunk_id_annotates_only_that_chunk() {
+    let env = TestEnv::new();
+    env.add("meeting #acme", "2024-03-05", "10:00", "11:00");
+    let task = env.stored_task("#acme", "meeting").unwrap();
+    let chunk_id = task["time_chunks"][0]["id"].as_str().unwrap();
+
+    env.run_ok(&["note", "--on", &chunk_id[..8], "sprint", "planning"]);
+
+    let task = env.stored_task("#acme", "meeting").unwrap();
+    assert_eq!(task["notes"].as_array().unwrap().len(), 0);
+    assert_eq!(
+        task["time_chunks"][0]["notes"][0]["text"],
+        "sprint planning"
+    );
+    let listing = env.run_ok(&["list"]);
+    assert!(
+        listing.contains("(chunk 1): sprint planning"),
+        "{}",
+        listing
+    );
+
+    env.run_ok(&["undo"]);
+    let task = env.stored_task("#acme", "meeting").unwrap();
+    assert!(task["time_chunks"][0].get("notes").is_none());
+}
+
+#[test]
+fn search_finds_notes_and_names_ignoring_case() {
+    let env = TestEnv::new();
+    env.add("meeting #acme", "2024-03-05", "10:00", "11:00");
+    env.run_ok(&["note", "--on", "meeting", "Discussed the Q3 budget"]);
+
+    let found = env.run_ok(&["search", "q3", "BUDGET"]);
+    assert!(found.contains(" meeting (#acme)"), "{}", found);
+    assert!(found.contains("  Discussed the Q3 budget"), "{}", found);
+
+    assert!(env.run_ok(&["search", "meet"]).contains(" meeting (#acme)"));
+    assert!(env
+        .run_ok(&["search", "payroll"])
+        .contains("Nothing matches"));
+}
+
+#[test]
+fn notes_persist_with_the_sqlite_backend() {
+    let env = TestEnv::new();
+    env.run_ok(&["configure", "--backend", "sqlite"]);
+    env.add("meeting #acme", "2024-03-05", "10:00", "11:00");
+    env.run_ok(&["note", "--on", "meeting", "agenda sent"]);
+
+    let chunk = env.run_ok(&["edit", "meeting"]);
+    let chunk_id = chunk
+        .lines()
+        .nth(1)
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap()
+        .to_string();
+    env.run_ok(&["note", "--on", &chunk_id, "ran long"]);
+
+    let found = env.run_ok(&["search", "agenda"]);
+    assert!(found.contains("agenda sent"), "{}", found);
+    assert!(env.run_ok(&["edit", "meeting"]).contains("ran long"));
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use common::TestEnv;
+use serde_json::Value;
+
+fn task(env: &TestEnv, category: &str, name: &str) -> Value {
+    env.stored_task(category, name)
+        .unwrap_or_else(|| panic!("no task {} in {}", name, category))
+}
+
+fn chunk_starts(task: &Value) -> Vec<&str> {
+    task["time_chunks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|chunk| &chunk["start_time"].as_str().unwrap()[11..16])
+        .collect()
+}
+
+#[test]
+fn rename_keeps_the_task_id_and_chunks() {
+    let env = TestEnv::new();
+    env.add("login fix #backend", "2024-03-05", "09:00", "10:00");
+    let before = task(&env, "#backend", "login fix");
+
+    env.run_ok(&["rename", "login fix", "auth", "flow"]);
+
+    let after = task(&env, "#backend", "auth flow");
+    assert_eq!(after["id"], before["id"]);
+    assert_eq!(after["time_chunks"], before["time_chunks"]);
+    assert!(env.stored_task("#backend", "login fix").is_none());
+}
+
+#[test]
+fn rename_refuses_a_name_already_taken() {
+    let env = TestEnv::new();
+    env.add("login", "2024-03-05", "09:00", "10:00");
+    env.add("auth", "2024-03-05", "11:00", "12:00");
+
+    assert!(!env.run(&["rename", "login", "auth"]).status.success());
+    task(&env, "Uncategorized", "login");
+}
+
+#[test]
+fn move_files_the_task_under_another_category() {
+    let env = TestEnv::new();
+    env.add("login", "2024-03-05", "09:00", "10:00");
+    let id = task(&env, "Uncategorized", "login")["id"].clone();
+
+    env.run_ok(&["move", "login", "#backend"]);
+
+    assert_eq!(task(&env, "#backend", "login")["id"], id);
+    assert!(env.stored_task("Uncategorized", "login").is_none());
+}
+
+#[test]
+fn merge_combines_chunks_in_time_order() {
+    let env = TestEnv::new();
+    env.add("login #backend", "2024-03-05", "11:00", "12:00");
+    env.add("auth #api", "2024-03-05", "09:00", "10:00");
+    env.add("auth #api", "2024-03-05", "13:00", "14:00");
+
+    env.run_ok(&["merge", "auth", "login"]);
+
+    assert_eq!(
+        chunk_starts(&task(&env, "#backend", "login")),
+        vec!["09:00", "11:00", "13:00"]
+    );
+    assert!(env.stored_task("#api", "auth").is_none());
+}
+
+#[test]
+fn category_rename_and_undo() {
+    let env = TestEnv::new();
+    env.add("login #api", "2024-03-05", "09:00", "10:00");
+
+    env.run_ok(&["category", "rename", "api", "#backend"]);
+    task(&env, "#backend", "login");
+
+    env.run_ok(&["undo"]);
+    task(&env, "#api", "login");
+    assert!(env.stored_task("#backend", "login").is_none());
+}
+
+#[test]
+fn category_merge_joins_tasks_with_the_same_name() {
+    let env = TestEnv::new();
+    env.add("login #api", "2024-03-05", "09:00", "10:00");
+    env.add("docs #api", "2024-03-05", "10:00", "11:00");
+    env.add("login #backend", "2024-03-05", "11:00", "12:00");
+
+    env.run_ok(&["category", "merge", "#api", "#backend"]);
+
+    assert_eq!(
+        chunk_starts(&task(&env, "#backend", "login")),
+        vec!["09:00", "11:00"]
+    );
+    task(&env, "#backend", "docs");
+    assert!(env.stored_task("#api", "docs").is_none());
+}
+
+#[test]
+fn category_delete_keeps_the_tasks_as_uncategorized() {
+    let env = TestEnv::new();
+    env.add("login #api", "2024-03-05", "09:00", "10:00");
+
+    env.run_ok(&["category", "delete", "#api"]);
+
+    task(&env, "Uncategorized", "login");
+    assert!(env.stored_task("#api", "login").is_none());
+}
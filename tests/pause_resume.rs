@@ -0,0 +1,119 @@
+mod common;
+
+use common::TestEnv;
+use serde_json::Value;
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+/// The only task in the data file.
+fn stored_task(env: &TestEnv) -> Value {
+    let contents = fs::read_to_string(env.data_file()).unwrap();
+    let value: Value = serde_json::from_str(&contents).unwrap();
+    let categories = value["categorization"]["categories"].as_object().unwrap();
+    let tasks: Vec<&Value> = categories
+        .values()
+        .flat_map(|tasks| tasks.as_array().unwrap())
+        .collect();
+    assert_eq!(tasks.len(), 1, "{}", contents);
+    tasks[0].clone()
+}
+
+#[test]
+fn pause_survives_to_the_next_command() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["pause"]);
+
+    let status = env.run_ok(&["status"]);
+
+    assert!(status.starts_with("Paused: "), "{}", status);
+    assert!(status.contains(" write docs (paused for"), "{}", status);
+    let task = stored_task(&env);
+    assert_eq!(task["status"], "Paused");
+    assert!(!task["time_chunks"][0]["end_time"].is_null());
+    assert_eq!(task["pauses"].as_array().unwrap().len(), 1);
+    assert!(task["pauses"][0]["end_time"].is_null());
+}
+
+#[test]
+fn resume_closes_the_pause_and_opens_a_chunk() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["pause"]);
+    thread::sleep(Duration::from_millis(1100));
+
+    env.run_ok(&["resume"]);
+
+    let task = stored_task(&env);
+    assert_eq!(task["status"], "Running");
+    assert_eq!(task["time_chunks"].as_array().unwrap().len(), 2);
+    assert!(task["time_chunks"][1]["end_time"].is_null());
+    assert_eq!(
+        task["pauses"][0]["end_time"],
+        task["time_chunks"][1]["start_time"]
+    );
+    assert!(task["paused_duration"]["secs"].as_u64().unwrap() >= 1);
+}
+
+#[test]
+fn resume_without_a_paused_task_changes_nothing() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    let before = fs::read_to_string(env.data_file()).unwrap();
+
+    let output = env.run_ok(&["resume"]);
+
+    assert!(output.contains("No paused task found."), "{}", output);
+    assert_eq!(fs::read_to_string(env.data_file()).unwrap(), before);
+}
+
+#[test]
+fn stopping_a_paused_task_ends_the_pause() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["pause"]);
+
+    let output = env.run_ok(&["stop"]);
+    assert!(output.contains("\"write docs\" is paused"), "{}", output);
+    assert_eq!(stored_task(&env)["status"], "Paused");
+
+    env.run_ok(&["stop", "write docs"]);
+
+    let task = stored_task(&env);
+    assert_eq!(task["status"], "Stopped");
+    assert_eq!(task["time_chunks"].as_array().unwrap().len(), 1);
+    assert!(!task["pauses"][0]["end_time"].is_null());
+}
+
+#[test]
+fn pauses_persist_with_the_sqlite_backend() {
+    let env = TestEnv::new();
+    env.run_ok(&["configure", "--backend", "sqlite"]);
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["pause"]);
+
+    assert!(env.run_ok(&["status"]).contains(" write docs (paused for"));
+
+    env.run_ok(&["resume"]);
+    let listing = env.run_ok(&["list"]);
+    assert!(listing.contains("Paused for"), "{}", listing);
+}
+
+#[test]
+fn starting_a_paused_task_ends_the_pause() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "alpha"]);
+    env.run_ok(&["pause"]);
+    env.run_ok(&["start", "alpha"]);
+    env.run_ok(&["pause"]);
+    env.run_ok(&["resume"]);
+
+    let task = stored_task(&env);
+    assert_eq!(task["status"], "Running");
+    let pauses = task["pauses"].as_array().unwrap();
+    assert_eq!(pauses.len(), 2);
+    for pause in pauses {
+        assert!(!pause["end_time"].is_null(), "{}", task);
+    }
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use common::TestEnv;
+
+#[test]
+fn start_without_a_task_and_no_terminal_starts_nothing() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["stop"]);
+
+    let output = env.run_ok(&["start"]);
+
+    assert!(output.contains("Nothing started"), "{}", output);
+    let task = env.stored_task("Uncategorized", "write docs").unwrap();
+    assert_eq!(task["status"], "Stopped");
+}
+
+#[test]
+fn edit_and_delete_without_a_task_and_no_terminal_fail() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["stop"]);
+
+    for command in ["edit", "delete"] {
+        let output = env.run(&[command]);
+        assert!(!output.status.success(), "{}", command);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("No task given"));
+    }
+    assert!(env.stored_task("Uncategorized", "write docs").is_some());
+}
+
+#[test]
+fn resume_without_a_terminal_falls_back_to_the_last_paused_task() {
+    let env = TestEnv::new();
+    env.run_ok(&["configure", "--running-policy", "pause"]);
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["start", "review"]);
+    env.run_ok(&["pause"]);
+
+    env.run_ok(&["resume"]);
+
+    let review = env.stored_task("Uncategorized", "review").unwrap();
+    assert_eq!(review["status"], "Running");
+    let docs = env.stored_task("Uncategorized", "write docs").unwrap();
+    assert_eq!(docs["status"], "Paused");
+}
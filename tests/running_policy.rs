@@ -0,0 +1,121 @@
+mod common;
+
+use common::TestEnv;
+use std::fs;
+
+#[test]
+fn starting_a_task_stops_the_running_one_by_default() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+
+    let output = env.run_ok(&["start", "review"]);
+
+    assert!(output.contains("Stopped \"write docs\""), "{}", output);
+    let docs = env.stored_task("Uncategorized", "write docs").unwrap();
+    let review = env.stored_task("Uncategorized", "review").unwrap();
+    assert_eq!(docs["status"], "Stopped");
+    assert_eq!(
+        docs["time_chunks"][0]["end_time"],
+        review["time_chunks"][0]["start_time"]
+    );
+}
+
+#[test]
+fn one_undo_restarts_the_stopped_task() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["start", "review"]);
+
+    env.run_ok(&["undo"]);
+
+    let docs = env.stored_task("Uncategorized", "write docs").unwrap();
+    assert_eq!(docs["status"], "Running");
+    assert!(env.stored_task("Uncategorized", "review").is_none());
+}
+
+#[test]
+fn auto_pause_lets_the_previous_task_be_resumed() {
+    let env = TestEnv::new();
+    env.run_ok(&["configure", "--running-policy", "pause"]);
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["start", "review"]);
+    let docs = env.stored_task("Uncategorized", "write docs").unwrap();
+    assert_eq!(docs["status"], "Paused");
+
+    env.run_ok(&["stop"]);
+    env.run_ok(&["resume"]);
+
+    let docs = env.stored_task("Uncategorized", "write docs").unwrap();
+    assert_eq!(docs["status"], "Running");
+    let review = env.stored_task("Uncategorized", "review").unwrap();
+    assert_eq!(review["status"], "Stopped");
+}
+
+#[test]
+fn resuming_applies_the_policy_too() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["pause"]);
+    env.run_ok(&["start", "review"]);
+
+    env.run_ok(&["resume", "write docs"]);
+
+    let review = env.stored_task("Uncategorized", "review").unwrap();
+    assert_eq!(review["status"], "Stopped");
+}
+
+#[test]
+fn parallel_tasks_must_be_stopped_by_name() {
+    let env = TestEnv::new();
+    env.run_ok(&["configure", "--running-policy", "parallel"]);
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["start", "review"]);
+
+    let output = env.run(&["stop"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Several tasks are running"));
+
+    env.run_ok(&["stop", "review"]);
+    let docs = env.stored_task("Uncategorized", "write docs").unwrap();
+    assert_eq!(docs["status"], "Running");
+}
+
+#[test]
+fn several_running_tasks_are_repaired_by_the_next_change() {
+    let env = TestEnv::new();
+    fs::create_dir_all(env.data_dir()).unwrap();
+    fs::write(
+        env.data_dir().join("tasks.json.events"),
+        concat!(
+            r#"{"id":1,"timestamp":"2024-03-05T09:00:00Z","user":"old","action":{"type":"Start","description":"write docs","at":"2024-03-05T09:00:00Z"}}"#,
+            "\n",
+            r#"{"id":2,"timestamp":"2024-03-05T10:00:00Z","user":"old","action":{"type":"Start","description":"review","at":"2024-03-05T10:00:00Z"}}"#,
+            "\n",
+        ),
+    )
+    .unwrap();
+    let events = fs::read_to_string(env.data_dir().join("tasks.json.events")).unwrap();
+
+    // Reading leaves the data as it is.
+    for command in ["status", "list", "log"] {
+        let output = env.run(&[command]);
+        assert!(output.status.success(), "{}", command);
+        assert!(output.stderr.is_empty(), "{}", command);
+    }
+    assert_eq!(
+        fs::read_to_string(env.data_dir().join("tasks.json.events")).unwrap(),
+        events
+    );
+
+    let output = env.run(&["stop", "review"]);
+
+    assert!(output.status.success());
+    let report = String::from_utf8_lossy(&output.stderr);
+    assert!(report.contains("Found 2 running tasks"), "{}", report);
+    assert!(report.contains("Stopped \"write docs\""), "{}", report);
+    let docs = env.stored_task("Uncategorized", "write docs").unwrap();
+    assert_eq!(docs["status"], "Stopped");
+    assert_eq!(docs["time_chunks"][0]["end_time"], "2024-03-05T10:00:00Z");
+    let second = env.run(&["status"]);
+    assert!(second.stderr.is_empty());
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::{listed_id, TestEnv};
+
+#[test]
+fn start_stop_start_reopens_the_same_task() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs", "#web"]);
+    let id = env.stored_task("#web", "write docs").unwrap()["id"].clone();
+    env.run_ok(&["stop"]);
+
+    let output = env.run_ok(&["start", "write", "docs", "#web"]);
+
+    assert!(output.contains("Reopening \"write docs\""), "{}", output);
+    let task = env.stored_task("#web", "write docs").unwrap();
+    assert_eq!(task["id"], id);
+    assert_eq!(task["status"], "Running");
+    let chunks = task["time_chunks"].as_array().unwrap();
+    assert_eq!(chunks.len(), 2);
+    assert!(!chunks[0]["end_time"].is_null());
+    assert!(chunks[1]["end_time"].is_null());
+}
+
+#[test]
+fn a_bare_name_reopens_the_task_in_its_category() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs", "#web"]);
+    env.run_ok(&["stop"]);
+
+    env.run_ok(&["start", "write", "docs"]);
+
+    assert!(env.stored_task("Uncategorized", "write docs").is_none());
+    let task = env.stored_task("#web", "write docs").unwrap();
+    assert_eq!(task["time_chunks"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn a_task_id_reopens_the_task() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs", "#web"]);
+    env.run_ok(&["stop"]);
+    let id = listed_id(&env.run_ok(&["list"]), "write docs");
+
+    env.run_ok(&["start", &id]);
+
+    let task = env.stored_task("#web", "write docs").unwrap();
+    assert_eq!(task["status"], "Running");
+    assert_eq!(task["time_chunks"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn a_near_miss_suggests_the_existing_task() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["stop"]);
+
+    let output = env.run_ok(&["start", "wirte", "docs"]);
+
+    assert!(output.contains("Did you mean \"write docs\""), "{}", output);
+    assert!(env.stored_task("Uncategorized", "wirte docs").is_none());
+
+    env.run_ok(&["start", "wirte", "docs", "--new"]);
+    assert!(env.stored_task("Uncategorized", "wirte docs").is_some());
+}
+
+#[test]
+fn starting_a_running_task_changes_nothing() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    let before = env.stored_task("Uncategorized", "write docs").unwrap();
+
+    let output = env.run_ok(&["start", "write", "docs"]);
+
+    assert!(output.contains("already running"), "{}", output);
+    assert_eq!(
+        env.stored_task("Uncategorized", "write docs").unwrap(),
+        before
+    );
+}
+
+#[test]
+fn start_stop_start_with_the_sqlite_backend() {
+    let env = TestEnv::new();
+    env.run_ok(&["configure", "--backend", "sqlite"]);
+    env.run_ok(&["start", "write", "docs"]);
+    env.run_ok(&["stop"]);
+
+    env.run_ok(&["start", "write", "docs"]);
+
+    let status = env.run_ok(&["status"]);
+    assert!(status.contains(" write docs"), "{}", status);
+    let listing = env.run_ok(&["list"]);
+    assert_eq!(listing.matches(" write docs - ").count(), 1, "{}", listing);
+}
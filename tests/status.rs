@@ -0,0 +1,133 @@
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use common::TestEnv;
+use fs2::FileExt;
+use serde_json::Value;
+use std::fs::{self, File};
+use std::thread;
+use std::time::Duration as StdDuration;
+
+fn status_json(env: &TestEnv) -> Value {
+    serde_json::from_str(&env.run_ok(&["status", "--json"])).unwrap()
+}
+
+#[test]
+fn format_fills_in_the_running_task() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs", "#web", "#urgent"]);
+
+    let line = env.run_ok(&["status", "--format", "{status} {name} ({category}) {tags}"]);
+    assert_eq!(line.trim_end(), "running write docs (#web) #urgent");
+
+    let elapsed = env.run_ok(&["status", "--format", "{elapsed}"]);
+    assert!(elapsed.trim_end().starts_with("00:00:0"), "{}", elapsed);
+}
+
+#[test]
+fn idle_has_empty_task_placeholders() {
+    let env = TestEnv::new();
+
+    let line = env.run_ok(&["status", "--format", "[{status}]{name}"]);
+    assert_eq!(line.trim_end(), "[idle]");
+
+    let status = status_json(&env);
+    assert_eq!(status["status"], "idle");
+    assert!(status["name"].is_null());
+}
+
+#[test]
+fn json_counts_what_was_tracked_today() {
+    let env = TestEnv::new();
+    let now = Utc::now().timestamp();
+    let midnight = Utc.timestamp_opt(now - now % 86_400, 0).unwrap();
+    let length = Duration::seconds((now % 86_400).min(600));
+    env.run_ok(&[
+        "add",
+        "standup",
+        "--from",
+        &midnight.to_rfc3339(),
+        "--to",
+        &(midnight + length).to_rfc3339(),
+    ]);
+    env.run_ok(&["start", "review"]);
+    env.run_ok(&["pause"]);
+
+    let status = status_json(&env);
+
+    assert_eq!(status["status"], "paused");
+    assert_eq!(status["name"], "review");
+    assert_eq!(status["today_seconds"], length.num_seconds());
+}
+
+#[test]
+fn status_lines_come_from_the_status_file_while_it_is_current() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    let status_file = env.data_dir().join("tasks.json.status");
+    assert!(status_file.exists());
+
+    fs::write(
+        &status_file,
+        r##"{"date":"2024-01-01","finished_today":0,"active":[{"name":"cached","category":"#x","tags":[],"status":"Running","since":"2024-01-01T00:00:00Z","finished":0}]}"##,
+    )
+    .unwrap();
+    assert_eq!(
+        env.run_ok(&["status", "--format", "{name}"]).trim_end(),
+        "cached"
+    );
+
+    fs::write(&status_file, "garbage").unwrap();
+    assert_eq!(
+        env.run_ok(&["status", "--format", "{name}"]).trim_end(),
+        "write docs"
+    );
+}
+
+#[test]
+fn missing_status_file_is_written_by_the_next_poll() {
+    let env = TestEnv::new();
+    env.install_fixture("v5.json");
+
+    // Upgrading the data file does not write a status file of its own.
+    let name = env.run_ok(&["status", "--format", "{name}"]);
+
+    assert_eq!(name.trim_end(), "");
+    let status_file = env.data_dir().join("tasks.json.status");
+    assert!(status_file.exists());
+    let data_written = fs::metadata(env.data_file()).unwrap().modified().unwrap();
+    let status_written = fs::metadata(&status_file).unwrap().modified().unwrap();
+    assert!(status_written >= data_written);
+}
+
+#[test]
+fn polls_show_the_last_status_while_the_data_is_locked() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    let status_file = env.data_dir().join("tasks.json.status");
+    let lock = File::create(env.data_dir().join("tasks.json.lock")).unwrap();
+    lock.lock_exclusive().unwrap();
+
+    // A data file newer than the status file would normally be loaded.
+    thread::sleep(StdDuration::from_millis(20));
+    let contents = fs::read(env.data_file()).unwrap();
+    fs::write(env.data_file(), contents).unwrap();
+    assert_eq!(
+        env.run_ok(&["status", "--format", "{name}"]).trim_end(),
+        "write docs"
+    );
+
+    fs::remove_file(&status_file).unwrap();
+    assert_eq!(
+        env.run_ok(&["status", "--format", "[{status}]"]).trim_end(),
+        "[idle]"
+    );
+    assert!(!status_file.exists());
+
+    lock.unlock().unwrap();
+    assert_eq!(
+        env.run_ok(&["status", "--format", "{name}"]).trim_end(),
+        "write docs"
+    );
+    assert!(status_file.exists());
+}
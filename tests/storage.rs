@@ -0,0 +1,136 @@
+mod common;
+
+use common::TestEnv;
+use fs2::FileExt;
+use std::fs::{self, File};
+
+#[test]
+fn sqlite_backend_keeps_everything_json_does() {
+    let env = TestEnv::new();
+    env.track_sample();
+    env.run_ok(&["note", "--on", "fix login", "sent the patch"]);
+    env.run_ok(&["start", "standup"]);
+    env.run_ok(&["pause"]);
+    let tracked = env.exported_rows(&[]);
+
+    env.run_ok(&["configure", "--backend", "sqlite"]);
+
+    assert!(env.data_dir().join("tasks.db").exists());
+    assert_eq!(env.exported_rows(&[]), tracked);
+    let status = env.run_ok(&["status"]);
+    assert!(status.contains(" standup (paused for"), "{}", status);
+
+    env.run_ok(&["resume"]);
+    env.run_ok(&["stop"]);
+    let with_sqlite = env.exported_rows(&[]);
+    assert_eq!(with_sqlite.len(), tracked.len() + 1);
+
+    // Moving back to JSON hands over what SQLite recorded.
+    fs::remove_file(env.data_file()).unwrap();
+    fs::remove_file(env.data_dir().join("tasks.json.events")).unwrap();
+    env.run_ok(&["configure", "--backend", "json"]);
+    assert_eq!(env.exported_rows(&[]), with_sqlite);
+}
+
+#[test]
+fn failed_write_leaves_the_previous_data_file_intact() {
+    let env = TestEnv::with_meeting();
+    let before = fs::read_to_string(env.data_file()).unwrap();
+    // Saving writes the temp file first; make that impossible.
+    fs::create_dir(env.data_dir().join("tasks.json.tmp")).unwrap();
+
+    let output = env.run(&["start", "coding"]);
+
+    assert!(!output.status.success());
+    assert_eq!(fs::read_to_string(env.data_file()).unwrap(), before);
+
+    fs::remove_dir(env.data_dir().join("tasks.json.tmp")).unwrap();
+    env.run_ok(&["stop"]);
+    assert!(!env.data_dir().join("tasks.json.tmp").exists());
+    assert!(env.stored_task("Uncategorized", "coding").is_some());
+}
+
+#[test]
+fn failed_save_is_reported_as_an_error() {
+    let env = TestEnv::with_meeting();
+    env.run_ok(&["start", "review"]);
+    fs::create_dir(env.data_dir().join("tasks.json.tmp")).unwrap();
+
+    // Each change still reaches the event log, so each one finds something
+    // to do.
+    for args in [
+        &["start", "coding"][..],
+        &["pause"],
+        &["stop", "coding"],
+        &["rename", "meeting", "standup"],
+        &[
+            "add",
+            "lunch",
+            "--date",
+            "2024-03-05",
+            "--from",
+            "12:00",
+            "--to",
+            "13:00",
+        ],
+        &["undo"],
+        &["clear"],
+    ] {
+        let output = env.run(args);
+
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.starts_with("Error: "), "{:?}: {}", args, stderr);
+        assert!(stderr.contains("data file"), "{:?}: {}", args, stderr);
+        assert!(!stderr.contains("panicked"), "{:?}: {}", args, stderr);
+    }
+}
+
+#[test]
+fn second_process_is_turned_away_while_the_data_is_locked() {
+    let env = TestEnv::with_meeting();
+    let before = fs::read_to_string(env.data_file()).unwrap();
+    let lock = File::create(env.data_dir().join("tasks.json.lock")).unwrap();
+    lock.lock_exclusive().unwrap();
+
+    let output = env.run(&["start", "coding"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("locked by another time_tracker process"),
+        "{}",
+        stderr
+    );
+    assert_eq!(fs::read_to_string(env.data_file()).unwrap(), before);
+
+    lock.unlock().unwrap();
+    env.run_ok(&["start", "coding"]);
+}
+
+#[test]
+fn corrupt_data_without_a_log_is_quarantined_until_recovered() {
+    let env = TestEnv::new();
+    fs::create_dir_all(env.data_dir()).unwrap();
+    fs::write(env.data_file(), "{\n  \"version\": 6,\n  oops\n}").unwrap();
+
+    let output = env.run(&["list"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("at line 3, column 3"), "{}", stderr);
+    assert!(!env.data_file().exists());
+    let marker = fs::read_to_string(env.data_dir().join("tasks.json.recovery")).unwrap();
+    assert!(fs::read_to_string(&marker).unwrap().contains("oops"));
+
+    let pending = env.run(&["start", "coding"]);
+    assert!(!pending.status.success());
+    assert!(String::from_utf8_lossy(&pending.stderr).contains("awaiting recovery"));
+
+    // A restore that does not parse changes nothing.
+    let broken = env.root().join("broken.json");
+    fs::write(&broken, "[").unwrap();
+    let refused = env.run(&["recover", "--restore", broken.to_str().unwrap()]);
+    assert!(!refused.status.success());
+    assert!(env.data_dir().join("tasks.json.recovery").exists());
+}
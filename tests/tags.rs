@@ -0,0 +1,130 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::TestEnv;
+use serde_json::json;
+use std::fs;
+
+/// Adds a finished entry that ended `ago` before now.
+fn add_recent(env: &TestEnv, description: &str, ago: Duration, length: Duration) {
+    let end = Utc::now() - ago;
+    env.run_ok(&[
+        "add",
+        description,
+        "--from",
+        &(end - length).to_rfc3339(),
+        "--to",
+        &end.to_rfc3339(),
+    ]);
+}
+
+#[test]
+fn first_hashtag_is_the_category_and_the_rest_are_tags() {
+    let env = TestEnv::new();
+
+    env.run_ok(&["start", "fix", "login", "#client-a", "#bugfix", "#urgent"]);
+
+    let task = env.stored_task("#client-a", "fix login").unwrap();
+    assert_eq!(task["tags"], json!(["bugfix", "urgent"]));
+    let listing = env.run_ok(&["list"]);
+    assert!(listing.contains("Tags: #bugfix #urgent"), "{}", listing);
+}
+
+#[test]
+fn list_only_shows_tasks_with_every_given_tag() {
+    let env = TestEnv::new();
+    add_recent(
+        &env,
+        "review #client-a #meeting",
+        Duration::hours(3),
+        Duration::hours(1),
+    );
+    add_recent(
+        &env,
+        "standup #client-b #meeting",
+        Duration::hours(1),
+        Duration::minutes(30),
+    );
+
+    let meetings = env.run_ok(&["list", "--tag", "meeting"]);
+    assert!(meetings.contains("review"), "{}", meetings);
+    assert!(meetings.contains("standup"), "{}", meetings);
+
+    let client_a = env.run_ok(&["list", "--tag", "#meeting", "--tag", "client-a"]);
+    assert!(client_a.contains("review"), "{}", client_a);
+    assert!(!client_a.contains("standup"), "{}", client_a);
+    assert!(!client_a.contains("#client-b"), "{}", client_a);
+}
+
+#[test]
+fn summary_counts_a_chunk_in_every_tag_it_carries() {
+    let env = TestEnv::new();
+    add_recent(
+        &env,
+        "review #client-a #meeting",
+        Duration::hours(3),
+        Duration::hours(1),
+    );
+    add_recent(
+        &env,
+        "standup #client-b #meeting",
+        Duration::hours(1),
+        Duration::minutes(30),
+    );
+
+    let summary = env.run_ok(&["summary", "day"]);
+
+    assert!(summary.contains("#meeting: 01h 30m 00s"), "{}", summary);
+    assert!(summary.contains("#client-a: 01h 00m 00s"), "{}", summary);
+    assert!(summary.contains("#client-b: 00h 30m 00s"), "{}", summary);
+
+    let filtered = env.run_ok(&["summary", "day", "--tag", "client-b"]);
+    assert!(filtered.contains("#meeting: 00h 30m 00s"), "{}", filtered);
+    assert!(!filtered.contains("#client-a"), "{}", filtered);
+}
+
+#[test]
+fn tag_adds_and_removes_tags() {
+    let env = TestEnv::new();
+    add_recent(
+        &env,
+        "review #client-a",
+        Duration::hours(1),
+        Duration::hours(1),
+    );
+
+    env.run_ok(&["tag", "review", "#meeting", "billable"]);
+    let task = env.stored_task("#client-a", "review").unwrap();
+    assert_eq!(task["tags"], json!(["billable", "meeting"]));
+
+    env.run_ok(&["tag", "review", "--remove", "meeting"]);
+    let task = env.stored_task("#client-a", "review").unwrap();
+    assert_eq!(task["tags"], json!(["billable"]));
+
+    env.run_ok(&["undo"]);
+    let task = env.stored_task("#client-a", "review").unwrap();
+    assert_eq!(task["tags"], json!(["billable", "meeting"]));
+
+    let output = env.run(&["tag", "review", "client-a"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn start_events_logged_before_tags_replay_unchanged() {
+    let env = TestEnv::new();
+    fs::create_dir_all(env.data_dir()).unwrap();
+    fs::write(
+        env.data_dir().join("tasks.json.events"),
+        concat!(
+            r#"{"id":1,"timestamp":"2024-03-05T10:00:00Z","user":"old","action":{"type":"Start","description":"fix #a #b","at":"2024-03-05T10:00:00Z"}}"#,
+            "\n",
+        ),
+    )
+    .unwrap();
+
+    let listing = env.run_ok(&["list"]);
+
+    assert!(listing.contains("Category: \"#a\""), "{}", listing);
+    assert!(listing.contains(" fix#b - "), "{}", listing);
+    assert!(!listing.contains("Tags:"), "{}", listing);
+}
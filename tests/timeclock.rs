@@ -0,0 +1,110 @@
+mod common;
+
+use common::TestEnv;
+use std::fs;
+
+#[test]
+fn chunks_become_clock_in_and_out_lines() {
+    let env = TestEnv::new();
+    env.track_sample();
+    env.run_ok(&["start", "standup"]);
+
+    let timeclock = env.run_ok(&["export", "--format", "timeclock"]);
+
+    let lines: Vec<&str> = timeclock.lines().collect();
+    assert_eq!(
+        lines[..6],
+        [
+            "i 2024-03-05 09:00:00 acme:web  fix login",
+            "o 2024-03-05 10:30:00",
+            "i 2024-03-05 11:00:00 internal  standup",
+            "o 2024-03-05 11:15:00",
+            "i 2024-03-29 23:30:00 Uncategorized  write docs",
+            "o 2024-03-29 23:59:00",
+        ]
+    );
+    // The running task is clocked in at the end.
+    assert_eq!(lines.len(), 9, "{}", timeclock);
+    assert!(lines[8].ends_with(" internal  standup"), "{}", timeclock);
+}
+
+#[test]
+fn timeclock_files_import_with_accounts_as_categories() {
+    let env = TestEnv::new();
+    let file = env.root().join("hours.timeclock");
+    fs::write(
+        &file,
+        "; hours for March\n\
+         i 2024/03/05 09:00:00 acme:web  fix login  ; ticket 42\n    ; pairing with Sam\n\
+         o 2024/03/05 10:30:00  ; done\n\
+         \n\
+         i 2024-03-05 11:00 internal\n\
+         o 2024-03-05 11:15\n\
+         i 2024-03-05 12:00:00 acme:web  review\n",
+    )
+    .unwrap();
+
+    let output = env.run_ok(&["import", "timeclock", file.to_str().unwrap(), "--yes"]);
+
+    assert!(output.contains("Imported 2 entries."), "{}", output);
+    let task = env.stored_task("#acme/web", "fix login").unwrap();
+    assert_eq!(task["time_chunks"][0]["start_time"], "2024-03-05T09:00:00Z");
+    assert_eq!(task["time_chunks"][0]["end_time"], "2024-03-05T10:30:00Z");
+    let notes: Vec<&str> = task["time_chunks"][0]["notes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|note| note["text"].as_str().unwrap())
+        .collect();
+    assert_eq!(notes, ["ticket 42", "pairing with Sam", "done"]);
+    assert!(env.stored_task("#internal", "internal").is_some());
+    assert!(env.stored_task("#acme/web", "review").is_none());
+}
+
+#[test]
+fn clocking_out_twice_is_an_error() {
+    let env = TestEnv::new();
+    let file = env.root().join("hours.timeclock");
+    fs::write(
+        &file,
+        "i 2024-03-05 09:00:00 internal\no 2024-03-05 10:00:00\no 2024-03-05 11:00:00\n",
+    )
+    .unwrap();
+
+    let output = env.run(&["import", "timeclock", file.to_str().unwrap(), "--yes"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Line 3: clocking out without clocking in"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn exported_timeclock_imports_back_without_losing_time() {
+    let env = TestEnv::new();
+    env.track_sample();
+    let standup = env.stored_task("#internal", "standup").unwrap();
+    let chunk_id = standup["time_chunks"][0]["id"].as_str().unwrap();
+    env.run_ok(&["note", "--on", &chunk_id[..8], "sprint", "planning"]);
+    env.run_ok(&["note", "--on", "write docs", "outline first"]);
+    let file = env.root().join("tracked.timeclock");
+    env.run_ok(&["export", "--format", "ledger", "-o", file.to_str().unwrap()]);
+    let timeclock = fs::read_to_string(&file).unwrap();
+    assert!(
+        timeclock.contains("internal  standup\n    ; sprint planning\no "),
+        "{}",
+        timeclock
+    );
+
+    let other = TestEnv::new();
+    other.run_ok(&["import", "hledger", file.to_str().unwrap(), "--yes"]);
+
+    // Timeclock has no room for tags.
+    assert_eq!(
+        other.exported_rows(&["task_id", "tags"]),
+        env.exported_rows(&["task_id", "tags"])
+    );
+}
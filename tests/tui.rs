@@ -0,0 +1,20 @@
+mod common;
+
+use common::TestEnv;
+
+#[test]
+fn tui_without_a_terminal_fails_cleanly() {
+    let env = TestEnv::new();
+    env.run_ok(&["start", "write", "docs"]);
+    let before = env.stored_task("Uncategorized", "write docs").unwrap();
+
+    let output = env.run(&["tui"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("needs a terminal"), "{}", stderr);
+    assert_eq!(
+        env.stored_task("Uncategorized", "write docs").unwrap(),
+        before
+    );
+}
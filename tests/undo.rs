@@ -0,0 +1,110 @@
+mod common;
+
+use common::TestEnv;
+
+#[test]
+fn undo_and_redo_round_trip() {
+    let env = TestEnv::with_meeting();
+    env.run_ok(&["start", "review"]);
+    env.run_ok(&["stop"]);
+
+    let undone = env.run_ok(&["undo"]);
+
+    assert!(
+        undone.contains("Undid #3: stop \"review\" (Uncategorized)"),
+        "{}",
+        undone
+    );
+    let task = env.stored_task("Uncategorized", "review").unwrap();
+    assert_eq!(task["status"], "Running");
+
+    let redone = env.run_ok(&["redo"]);
+
+    assert!(
+        redone.contains("Redid #3: stop \"review\" (Uncategorized)"),
+        "{}",
+        redone
+    );
+    let task = env.stored_task("Uncategorized", "review").unwrap();
+    assert_eq!(task["status"], "Stopped");
+    assert!(env.run_ok(&["redo"]).contains("Nothing left to redo."));
+}
+
+#[test]
+fn undo_and_redo_several_changes_at_once() {
+    let env = TestEnv::with_meeting();
+    env.run_ok(&["start", "review"]);
+    env.run_ok(&["stop"]);
+
+    env.run_ok(&["undo", "2"]);
+    assert!(env.stored_task("Uncategorized", "review").is_none());
+
+    env.run_ok(&["redo", "2"]);
+    let task = env.stored_task("Uncategorized", "review").unwrap();
+    assert_eq!(task["status"], "Stopped");
+}
+
+#[test]
+fn new_change_after_undo_discards_redo() {
+    let env = TestEnv::with_meeting();
+    env.run_ok(&["start", "review"]);
+    env.run_ok(&["undo"]);
+
+    env.run_ok(&["start", "write docs"]);
+
+    assert!(env.run_ok(&["redo"]).contains("Nothing left to redo."));
+    assert!(env.stored_task("Uncategorized", "review").is_none());
+    assert!(env.stored_task("Uncategorized", "write docs").is_some());
+}
+
+#[test]
+fn undo_stops_at_the_data_that_predates_the_log() {
+    let env = TestEnv::new();
+    env.install_fixture("v6.json");
+    env.run_ok(&["start", "review"]);
+
+    let undone = env.run_ok(&["undo", "5"]);
+
+    assert!(undone.contains("Undid #2: start \"review\""), "{}", undone);
+    assert!(undone.contains("Nothing left to undo."), "{}", undone);
+    assert!(!undone.contains("Undid #1"), "{}", undone);
+    assert!(env.stored_task("Uncategorized", "review").is_none());
+    assert!(env.stored_task("#backend", "fix login").is_some());
+    assert!(env.stored_task("Uncategorized", "email").is_some());
+}
+
+#[test]
+fn undo_everything_in_a_fresh_log() {
+    let env = TestEnv::with_meeting();
+
+    assert!(env.run_ok(&["undo"]).contains("Undid #1: add \"meeting\""));
+    assert!(env.run_ok(&["undo"]).contains("Nothing left to undo."));
+    assert!(env.stored_task("Uncategorized", "meeting").is_none());
+}
+
+#[test]
+fn history_marks_undone_changes() {
+    let env = TestEnv::with_meeting();
+    env.run_ok(&["start", "review"]);
+    env.run_ok(&["stop"]);
+    env.run_ok(&["undo"]);
+
+    let history = env.run_ok(&["history"]);
+
+    assert!(
+        history.contains(" tester stop \"review\" (Uncategorized) (undone)"),
+        "{}",
+        history
+    );
+    assert!(
+        history.contains("    \"review\" (Uncategorized): none -> Running, 1 chunks,"),
+        "{}",
+        history
+    );
+    assert!(
+        !history.contains("start \"review\" (undone)"),
+        "{}",
+        history
+    );
+    assert!(history.contains("    0 tasks -> 1 tasks"), "{}", history);
+}